@@ -0,0 +1,130 @@
+use lol;
+use common::discord_bot_types::{self, BudgetAction};
+use common::playtime_budgets::PlaytimeBudget;
+use common::storage::BotStorage;
+use crate::lol_command;
+use crate::models;
+
+pub async fn execute_budget_command(
+    lol_api_fetcher: &lol::api_fetcher::BoundedHttpFetcher,
+    storage: &dyn BotStorage,
+    command: &discord_bot_types::BudgetCommand) -> Result<String, discord_bot_types::BotError> {
+
+    match command.action {
+        BudgetAction::Set => return set_budget(lol_api_fetcher, storage, command).await,
+        BudgetAction::View => return view_budget(lol_api_fetcher, storage, command).await,
+        BudgetAction::Clear => {
+            common::playtime_budgets::clear_budget(storage, &command.discord_user_id).await.map_err(to_storage_bot_error)?;
+            return Ok("Your playtime budget is cleared. Play as much as you like.".to_string());
+        }
+    }
+}
+
+async fn set_budget(
+    lol_api_fetcher: &lol::api_fetcher::BoundedHttpFetcher,
+    storage: &dyn BotStorage,
+    command: &discord_bot_types::BudgetCommand) -> Result<String, discord_bot_types::BotError> {
+
+    let (puuid, player_name) = match find_account(lol_api_fetcher, storage, command).await? {
+        None => return Ok("Choose the League account to budget, or use /link to link yours in this server first.".to_string()),
+        Some(account) => account
+    };
+
+    let hours = command.hours.unwrap_or(0);
+    let channel_id = if command.dm {None} else {command.channel_id.clone()};
+
+    let budget = PlaytimeBudget {
+        discord_user_id: command.discord_user_id.to_string(),
+        puuid: puuid,
+        player_name: player_name,
+        period: command.period,
+        limit_millis: hours * 3600000,
+        set_at_millis: common::date_range::now_millis().map_err(models::to_clock_bot_error)?,
+        channel_id: channel_id,
+        warned_period_start: None,
+        last_checked_match_id: None
+    };
+
+    common::playtime_budgets::store_budget(storage, &budget).await.map_err(to_storage_bot_error)?;
+
+    return Ok(format!(
+        "Your {} budget for {} is {} {}. I'll warn you {} if you go over it.",
+        budget.period.name(),
+        budget.player_name,
+        hours,
+        if hours == 1 {"hour"} else {"hours"},
+        create_warning_destination_string(&budget)
+    ));
+}
+
+async fn view_budget(
+    lol_api_fetcher: &lol::api_fetcher::BoundedHttpFetcher,
+    storage: &dyn BotStorage,
+    command: &discord_bot_types::BudgetCommand) -> Result<String, discord_bot_types::BotError> {
+
+    let budget = match common::playtime_budgets::get_budget(storage, &command.discord_user_id).await.map_err(to_storage_bot_error)? {
+        None => return Ok("You don't have a playtime budget. Use /budget with the set action to add one.".to_string()),
+        Some(budget) => budget
+    };
+
+    let api_key = lol_command::get_api_key()?;
+    let now = common::date_range::now_seconds().map_err(models::to_clock_bot_error)?;
+    let game_ids = lol::get_game_ids(lol_api_fetcher, &api_key, "europe", &budget.puuid, &budget.period.range(now), &lol::models::GameIdsFilter::default()).await
+        .map_err(models::to_bot_error)?;
+    let games = lol::fetch_game_summaries(lol_api_fetcher, &api_key, "europe", &budget.puuid, game_ids).await
+        .map_err(models::to_bot_error)?;
+
+    let played_millis = lol_command::calculate_time_played(&games);
+    let percentage = if budget.limit_millis > 0 {played_millis * 100 / budget.limit_millis} else {0};
+
+    return Ok(format!(
+        "Your {} budget for {} is {}. You've played {} {} ({}% of it). I'll warn you {} if you go over it.",
+        budget.period.name(),
+        budget.player_name,
+        common::date_range::format_duration(budget.limit_millis),
+        common::date_range::format_duration(played_millis),
+        budget.period.description(),
+        percentage,
+        create_warning_destination_string(&budget)
+    ));
+}
+
+/**
+ * The PUUID and name of the account the user asked to budget, or the one they linked in the server if they didn't say
+ */
+async fn find_account(
+    lol_api_fetcher: &lol::api_fetcher::BoundedHttpFetcher,
+    storage: &dyn BotStorage,
+    command: &discord_bot_types::BudgetCommand) -> Result<Option<(String, String)>, discord_bot_types::BotError> {
+
+    if !command.player_name.is_empty() {
+        let api_key = lol_command::get_api_key()?;
+        let puuid = lol::get_puuid(lol_api_fetcher, "euw1", &command.player_name, &api_key).await.map_err(models::to_bot_error)?;
+        return Ok(Some((puuid, command.player_name.to_string())));
+    }
+
+    let guild_id = match &command.guild_id {
+        None => return Ok(None),
+        Some(guild_id) => guild_id
+    };
+
+    let accounts = common::linked_accounts::get_linked_accounts(storage, guild_id).await.map_err(to_storage_bot_error)?;
+
+    return Ok(accounts.into_iter()
+        .find(|account| account.discord_user_id == command.discord_user_id)
+        .map(|account| (account.puuid, account.player_name)));
+}
+
+fn create_warning_destination_string(budget: &PlaytimeBudget) -> String {
+    return match &budget.channel_id {
+        None => "by DM".to_string(),
+        Some(channel_id) => format!("in <#{}>", channel_id)
+    };
+}
+
+fn to_storage_bot_error(err: common::storage::StorageError) -> discord_bot_types::BotError {
+    return discord_bot_types::BotError {
+        statusCode: 500,
+        body: format!("Could not update playtime budget: {}", err)
+    };
+}
@@ -0,0 +1,398 @@
+use lol;
+use common::discord_bot_types;
+use common::storage::BotStorage;
+use crate::lol_command;
+use crate::models;
+use crate::progress::ProgressReporter;
+use crate::rank_command;
+use futures::stream::{self, StreamExt};
+use std::collections::HashSet;
+use tokio::time::{timeout_at, Instant};
+
+/**
+ * How wide each player's column in the comparison table is. Longer names are cut short.
+ */
+const COLUMN_WIDTH: usize = 22;
+
+/**
+ * What we found out about one of the players being compared
+ */
+struct PlayerComparison {
+    player_name: String,
+    puuid: String,
+    games: Vec<lol::models::UserGameSummary>,
+    rank: Option<models::RankingWithChange>
+}
+
+pub async fn execute_compare_command(
+    lol_api_fetcher: &lol::api_fetcher::BoundedHttpFetcher,
+    storage: &dyn BotStorage,
+    command: &discord_bot_types::CompareCommand,
+    deadline: Instant,
+    progress: &ProgressReporter) -> Result<String, discord_bot_types::BotError> {
+
+    let api_key = lol_command::get_api_key()?;
+    let (range, truncated) = common::date_range::clamp_to_match_history(&command.range);
+
+    let (first_puuid, second_puuid) = tokio::try_join!(
+        lol::get_puuid(lol_api_fetcher, "euw1", &command.player_name, &api_key),
+        lol::get_puuid(lol_api_fetcher, "euw1", &command.other_player_name, &api_key)
+    ).map_err(models::to_bot_error)?;
+
+    let games = get_both_players_games(lol_api_fetcher, &api_key, &first_puuid, &second_puuid, &range, deadline, progress);
+    let ranks = async {
+        tokio::try_join!(
+            get_solo_queue_ranking(lol_api_fetcher, storage, &api_key, &command.player_name, range.start),
+            get_solo_queue_ranking(lol_api_fetcher, storage, &api_key, &command.other_player_name, range.start)
+        )
+    };
+
+    let ((first_games, second_games, fetched_games, total_games), (first_rank, second_rank)) = tokio::try_join!(games, ranks)?;
+
+    let first = PlayerComparison {
+        player_name: command.player_name.to_string(),
+        puuid: first_puuid,
+        games: first_games,
+        rank: first_rank
+    };
+
+    let second = PlayerComparison {
+        player_name: command.other_player_name.to_string(),
+        puuid: second_puuid,
+        games: second_games,
+        rank: second_rank
+    };
+
+    let mut message = format!("**{}** vs **{}** {}\n", first.player_name, second.player_name, range.description);
+
+    message.push_str(&lol_command::create_truncated_range_string(truncated));
+    message.push_str(&lol_command::create_partial_results_string(fetched_games, total_games));
+
+    message.push_str(&create_comparison_table_string(&first, &second));
+    message.push_str(&create_most_played_string(&first));
+    message.push_str(&create_most_played_string(&second));
+    message.push_str(&create_shared_games_string(&first, &second));
+
+    return Ok(message);
+}
+
+/**
+ * Fetches both players' games in the range. Games they played in together are only fetched once. Returns each
+ * player's games, along with how many games we managed to fetch out of how many there were before the deadline.
+ */
+async fn get_both_players_games(
+    lol_api_fetcher: &lol::api_fetcher::BoundedHttpFetcher,
+    api_key: &str,
+    first_puuid: &str,
+    second_puuid: &str,
+    range: &common::date_range::DateRange,
+    deadline: Instant,
+    progress: &ProgressReporter) -> Result<(Vec<lol::models::UserGameSummary>, Vec<lol::models::UserGameSummary>, usize, usize), discord_bot_types::BotError> {
+
+    let every_game = lol::models::GameIdsFilter::default();
+    let (first_game_ids, second_game_ids) = tokio::try_join!(
+        lol::get_game_ids(lol_api_fetcher, api_key, "europe", first_puuid, range, &every_game),
+        lol::get_game_ids(lol_api_fetcher, api_key, "europe", second_puuid, range, &every_game)
+    ).map_err(models::to_bot_error)?;
+
+    let (shared_game_ids, second_only_game_ids) = split_shared_game_ids(&first_game_ids, second_game_ids);
+
+    let total_games = first_game_ids.len() + second_only_game_ids.len();
+    progress.games_found(total_games);
+
+    let mut summaries_stream = stream::select(
+        lol::stream_game_summaries(lol_api_fetcher, api_key, "europe", first_puuid, first_game_ids),
+        lol::stream_game_summaries(lol_api_fetcher, api_key, "europe", second_puuid, second_only_game_ids)
+    );
+
+    let mut first_games: Vec<lol::models::UserGameSummary> = Vec::new();
+    let mut second_games: Vec<lol::models::UserGameSummary> = Vec::new();
+
+    loop {
+        match timeout_at(deadline, summaries_stream.next()).await {
+            Ok(Some(summary)) => {
+                let summary = summary.map_err(models::to_bot_error)?;
+                progress.game_fetched();
+
+                if summary.participant.puuid == first_puuid {
+                    first_games.push(summary);
+                } else {
+                    second_games.push(summary);
+                }
+            },
+            Ok(None) => break,
+            Err(_) => {
+                println!("Deadline reached after fetching {} of {} games", first_games.len() + second_games.len(), total_games);
+                break;
+            }
+        }
+    }
+
+    let fetched_games = first_games.len() + second_games.len();
+
+    second_games.extend(create_shared_games(&first_games, &shared_game_ids, second_puuid));
+
+    return Ok((first_games, second_games, fetched_games, total_games));
+}
+
+/**
+ * Splits the second player's game IDs into the ones they shared with the first player and the ones only they played
+ */
+fn split_shared_game_ids(first_game_ids: &[String], second_game_ids: Vec<String>) -> (HashSet<String>, Vec<String>) {
+    let first_game_id_set: HashSet<&String> = first_game_ids.iter().collect();
+    let (shared_game_ids, second_only_game_ids): (Vec<String>, Vec<String>) = second_game_ids.into_iter()
+        .partition(|game_id| first_game_id_set.contains(game_id));
+
+    return (shared_game_ids.into_iter().collect(), second_only_game_ids);
+}
+
+/**
+ * The second player's view of the games they shared with the first player, which comes from the first player's copy
+ * so that those games are only fetched once
+ */
+fn create_shared_games(
+    first_games: &[lol::models::UserGameSummary],
+    shared_game_ids: &HashSet<String>,
+    second_puuid: &str) -> Vec<lol::models::UserGameSummary> {
+
+    return first_games.iter()
+        .filter(|game| shared_game_ids.contains(&game.match_id))
+        .filter_map(|game| {
+            let participant = game.participants.iter().find(|participant| participant.puuid == second_puuid)?;
+
+            Some(lol::models::UserGameSummary {
+                game_id: game.game_id,
+                match_id: game.match_id.to_string(),
+                participant: participant.clone(),
+                game_duration_millis: game.game_duration_millis,
+                game_end_timestamp: game.game_end_timestamp,
+                participants: game.participants.clone()
+            })
+        })
+        .collect();
+}
+
+async fn get_solo_queue_ranking(
+    lol_api_fetcher: &lol::api_fetcher::BoundedHttpFetcher,
+    storage: &dyn BotStorage,
+    api_key: &str,
+    player_name: &str,
+    since: u64) -> Result<Option<models::RankingWithChange>, discord_bot_types::BotError> {
+
+    let rankings = rank_command::fetch_and_record_rankings(lol_api_fetcher, storage, api_key, player_name, since).await?;
+    return Ok(rankings.into_iter().find(|ranking| ranking.entry.queueType == "RANKED_SOLO_5x5"));
+}
+
+fn create_comparison_table_string(first: &PlayerComparison, second: &PlayerComparison) -> String {
+    let row_names = ["Rank", "Games", "Winrate", "KDA", "CS/min", "Played for"];
+    let first_values = create_comparison_values(first);
+    let second_values = create_comparison_values(second);
+
+    let mut result = "```\n".to_string();
+    result.push_str(&format!("{:<12}{:<width$}{:<width$}\n", "", truncate(&first.player_name), truncate(&second.player_name), width = COLUMN_WIDTH));
+
+    for ((name, first_value), second_value) in row_names.iter().zip(first_values.iter()).zip(second_values.iter()) {
+        result.push_str(&format!("{:<12}{:<width$}{:<width$}\n", name, truncate(first_value), truncate(second_value), width = COLUMN_WIDTH));
+    }
+
+    result.push_str("```\n");
+    return result;
+}
+
+/**
+ * The player's column of the comparison table, in the same order as the table's rows
+ */
+fn create_comparison_values(player: &PlayerComparison) -> Vec<String> {
+    let rank = match &player.rank {
+        None => "Unranked".to_string(),
+        Some(ranking) => format!("{} {} LP", lol::rank::format_rank(&ranking.entry), ranking.entry.leaguePoints)
+    };
+
+    let winrate = lol::rank::winrate_percentage(lol_command::calculate_wins(&player.games), lol_command::calculate_loses(&player.games));
+
+    return vec![
+        rank,
+        player.games.len().to_string(),
+        format!("{}%", winrate),
+        format!("{:.2}", calculate_kda(&player.games)),
+        format!("{:.1}", calculate_creep_score_per_minute(&player.games)),
+        create_short_time_played_string(lol_command::calculate_time_played(&player.games))
+    ];
+}
+
+/**
+ * e.g. 'Alice mostly played Ahri (5), Lux (3), Zed (2)'
+ */
+fn create_most_played_string(player: &PlayerComparison) -> String {
+    let champions = lol_command::calculate_champion_summaries(&player.games);
+
+    if champions.is_empty() {
+        return format!("{} didn't play any games\n", player.player_name);
+    }
+
+    let most_played = champions.iter()
+        .take(3)
+        .map(|champion| format!("{} ({})", champion.champion_name, champion.games))
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    return format!("{} mostly played {}\n", player.player_name, most_played);
+}
+
+/**
+ * How the players did in the games they were both in, on the same team or against each other
+ */
+fn create_shared_games_string(first: &PlayerComparison, second: &PlayerComparison) -> String {
+    let mut together_wins: u64 = 0;
+    let mut together_losses: u64 = 0;
+    let mut first_wins_against: u64 = 0;
+    let mut second_wins_against: u64 = 0;
+
+    for game in first.games.iter() {
+        let other = match game.participants.iter().find(|participant| participant.puuid == second.puuid) {
+            None => continue,
+            Some(other) => other
+        };
+
+        match (other.teamId == game.participant.teamId, game.participant.win) {
+            (true, true) => together_wins += 1,
+            (true, false) => together_losses += 1,
+            (false, true) => first_wins_against += 1,
+            (false, false) => second_wins_against += 1
+        }
+    }
+
+    let mut result = "".to_string();
+    let together = together_wins + together_losses;
+    let against = first_wins_against + second_wins_against;
+
+    if together == 0 && against == 0 {
+        result.push_str("They haven't played any games together\n");
+        return result;
+    }
+
+    if together > 0 {
+        result.push_str(&format!(
+            "Played together in {} {}: {}W {}L ({}% winrate)\n",
+            together,
+            if together == 1 {"game"} else {"games"},
+            together_wins,
+            together_losses,
+            lol::rank::winrate_percentage(together_wins, together_losses)
+        ));
+    }
+
+    if against > 0 {
+        result.push_str(&format!(
+            "Played against each other in {} {}: {} won {}, {} won {}\n",
+            against,
+            if against == 1 {"game"} else {"games"},
+            first.player_name,
+            first_wins_against,
+            second.player_name,
+            second_wins_against
+        ));
+    }
+
+    return result;
+}
+
+fn calculate_kda(games: &Vec<lol::models::UserGameSummary>) -> f64 {
+    let kills: u64 = games.iter().map(|game| game.participant.kills).sum();
+    let deaths: u64 = games.iter().map(|game| game.participant.deaths).sum();
+    let assists: u64 = games.iter().map(|game| game.participant.assists).sum();
+
+    return lol::stats::kda(kills, deaths, assists);
+}
+
+fn calculate_creep_score_per_minute(games: &Vec<lol::models::UserGameSummary>) -> f64 {
+    let creep_score: u64 = games.iter().map(|game| game.participant.totalMinionsKilled + game.participant.neutralMinionsKilled).sum();
+    return lol::stats::creep_score_per_minute(creep_score, lol_command::calculate_time_played(games));
+}
+
+/**
+ * e.g. '12h 5m', since the full 'hours and minutes' form doesn't fit in the table
+ */
+fn create_short_time_played_string(millis: u64) -> String {
+    let minutes = millis / 60000;
+    return format!("{}h {}m", minutes / 60, minutes % 60);
+}
+
+fn truncate(value: &str) -> String {
+    return value.chars().take(COLUMN_WIDTH - 1).collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn participant(puuid: &str, team_id: u64, win: bool) -> lol::models::Participant {
+        return serde_json::from_value(serde_json::json!({
+            "championName": "Ahri",
+            "puuid": puuid,
+            "win": win,
+            "kills": 1,
+            "deaths": 2,
+            "assists": 3,
+            "teamId": team_id
+        })).unwrap();
+    }
+
+    /**
+     * The first player's copy of a game, which the other participants were also in
+     */
+    fn game(match_id: &str, others: Vec<lol::models::Participant>) -> lol::models::UserGameSummary {
+        let player = participant("first", 100, true);
+        let mut participants = vec![player.clone()];
+        participants.extend(others);
+
+        return lol::models::UserGameSummary {
+            game_id: 1,
+            match_id: match_id.to_string(),
+            participant: player,
+            game_duration_millis: 1800000,
+            game_end_timestamp: 1715774400000,
+            participants: participants
+        };
+    }
+
+    fn ids(game_ids: Vec<&str>) -> Vec<String> {
+        return game_ids.into_iter().map(|game_id| game_id.to_string()).collect();
+    }
+
+    #[test]
+    fn splits_off_the_games_both_players_were_in() {
+        let (shared, second_only) = split_shared_game_ids(&ids(vec!["EUW1_1", "EUW1_2", "EUW1_3"]), ids(vec!["EUW1_2", "EUW1_4", "EUW1_3"]));
+
+        assert_eq!(shared, ids(vec!["EUW1_2", "EUW1_3"]).into_iter().collect());
+        assert_eq!(second_only, ids(vec!["EUW1_4"]));
+    }
+
+    #[test]
+    fn takes_the_second_players_view_of_shared_games_from_the_first_players_copy() {
+        let first_games = vec![
+            game("EUW1_1", vec![participant("second", 200, false)]),
+            game("EUW1_2", vec![participant("second", 100, true)]),
+            game("EUW1_3", vec![participant("someone", 200, false)])
+        ];
+        let shared = ids(vec!["EUW1_1", "EUW1_2"]).into_iter().collect();
+
+        let second_games = create_shared_games(&first_games, &shared, "second");
+
+        assert_eq!(second_games.len(), 2);
+        assert_eq!(second_games[0].match_id, "EUW1_1");
+        assert_eq!(second_games[0].participant.puuid, "second");
+        assert!(!second_games[0].participant.win);
+        assert_eq!(second_games[0].participants.len(), 2);
+        assert_eq!(second_games[1].match_id, "EUW1_2");
+        assert!(second_games[1].participant.win);
+    }
+
+    #[test]
+    fn skips_shared_games_the_second_player_is_missing_from() {
+        let first_games = vec![game("EUW1_1", vec![participant("someone", 200, false)])];
+        let shared = ids(vec!["EUW1_1"]).into_iter().collect();
+
+        assert!(create_shared_games(&first_games, &shared, "second").is_empty());
+    }
+}
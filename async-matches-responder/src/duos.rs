@@ -0,0 +1,170 @@
+use lol;
+use common::linked_accounts::LinkedAccount;
+use std::collections::HashMap;
+
+/**
+ * Someone only counts as a duo partner once they've been on the player's team in this many games, so that random
+ * teammates from solo queue aren't listed
+ */
+const MIN_GAMES_TOGETHER: u64 = 2;
+
+/**
+ * Only the most frequent partners are listed
+ */
+const MAX_DUO_PARTNERS: usize = 5;
+
+pub struct DuoPartner {
+    pub puuid: String,
+
+    /**
+     * The partner's Riot ID as of the most recent game they played together
+     */
+    pub riot_id: String,
+    pub games: u64,
+    pub wins: u64
+}
+
+/**
+ * Finds the people who were on the player's team in more than one of the given games, most frequent first
+ */
+pub fn find_duo_partners(games: &Vec<lol::models::UserGameSummary>) -> Vec<DuoPartner> {
+    let mut partners: HashMap<String, DuoPartner> = HashMap::new();
+
+    // Games are newest first, so the first Riot ID we see for a partner is their current one
+    for game in games.iter() {
+        let teammates = game.participants.iter()
+            .filter(|participant| participant.teamId == game.participant.teamId && participant.puuid != game.participant.puuid);
+
+        for teammate in teammates {
+            let partner = partners.entry(teammate.puuid.to_string()).or_insert(DuoPartner {
+                puuid: teammate.puuid.to_string(),
+                riot_id: format!("{}#{}", teammate.riotIdGameName, teammate.riotIdTagline),
+                games: 0,
+                wins: 0
+            });
+
+            partner.games += 1;
+            partner.wins += if game.participant.win {1} else {0};
+        }
+    }
+
+    let mut result: Vec<DuoPartner> = partners.into_values()
+        .filter(|partner| partner.games >= MIN_GAMES_TOGETHER)
+        .collect();
+
+    result.sort_by(|a, b| b.games.cmp(&a.games).then(b.wins.cmp(&a.wins)).then(a.riot_id.cmp(&b.riot_id)));
+    result.truncate(MAX_DUO_PARTNERS);
+
+    return result;
+}
+
+/**
+ * e.g. 'Played with: Bob#EUW (<@1234>) 5 games, 80% winrate; Carol#EUW 3 games, 33% winrate'. Partners who have
+ * linked their account in the server are mentioned, which doesn't notify them since our messages allow no mentions.
+ */
+pub fn create_duo_partners_string(partners: &Vec<DuoPartner>, linked_accounts: &Vec<LinkedAccount>) -> String {
+    if partners.is_empty() {
+        return "".to_string();
+    }
+
+    let partner_strings = partners.iter().map(|partner| {
+        let member = linked_accounts.iter()
+            .find(|account| account.puuid == partner.puuid)
+            .map(|account| format!(" (<@{}>)", account.discord_user_id))
+            .unwrap_or("".to_string());
+
+        format!(
+            "{}{} {} games, {}% winrate",
+            partner.riot_id,
+            member,
+            partner.games,
+            lol::rank::winrate_percentage(partner.wins, partner.games - partner.wins)
+        )
+    }).collect::<Vec<String>>();
+
+    return format!("Played with: {}\n", partner_strings.join("; "));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn participant(puuid: &str, riot_id_game_name: &str, team_id: u64, win: bool) -> lol::models::Participant {
+        return serde_json::from_value(serde_json::json!({
+            "championName": "Ahri",
+            "puuid": puuid,
+            "win": win,
+            "kills": 0,
+            "deaths": 0,
+            "assists": 0,
+            "teamId": team_id,
+            "riotIdGameName": riot_id_game_name,
+            "riotIdTagline": "EUW"
+        })).unwrap();
+    }
+
+    /**
+     * A game the player played with the given teammates (PUUID and Riot ID name) against one opponent
+     */
+    fn game(game_id: u64, win: bool, teammates: &[(&str, &str)]) -> lol::models::UserGameSummary {
+        let player = participant("player", "Player", 100, win);
+
+        let mut participants = vec![player.clone(), participant("opponent", "Opponent", 200, !win)];
+        participants.extend(teammates.iter().map(|(puuid, name)| participant(puuid, name, 100, win)));
+
+        return lol::models::UserGameSummary {
+            game_id: game_id,
+            match_id: format!("EUW1_{}", game_id),
+            participant: player,
+            game_duration_millis: 1800000,
+            game_end_timestamp: game_id,
+            participants: participants
+        };
+    }
+
+    fn account(discord_user_id: &str, puuid: &str) -> LinkedAccount {
+        return LinkedAccount {
+            discord_user_id: discord_user_id.to_string(),
+            puuid: puuid.to_string(),
+            player_name: "Linked#EUW".to_string()
+        };
+    }
+
+    #[test]
+    fn finds_teammates_from_more_than_one_game() {
+        let games = vec![
+            game(3, true, &[("bob", "Bob"), ("carol", "Carol"), ("random1", "Random")]),
+            game(2, false, &[("bob", "Bob"), ("carol", "Carol")]),
+            game(1, true, &[("bob", "Bob"), ("random2", "Random")])
+        ];
+
+        let partners = find_duo_partners(&games);
+
+        assert_eq!(partners.iter().map(|partner| partner.puuid.as_str()).collect::<Vec<_>>(), vec!["bob", "carol"]);
+        assert_eq!((partners[0].games, partners[0].wins), (3, 2));
+        assert_eq!((partners[1].games, partners[1].wins), (2, 1));
+    }
+
+    #[test]
+    fn uses_the_riot_id_from_the_newest_game() {
+        let games = vec![game(2, true, &[("bob", "Robert")]), game(1, true, &[("bob", "Bob")])];
+
+        assert_eq!(find_duo_partners(&games)[0].riot_id, "Robert#EUW");
+    }
+
+    #[test]
+    fn mentions_partners_linked_in_the_server() {
+        let games = vec![
+            game(3, true, &[("bob", "Bob"), ("carol", "Carol")]),
+            game(2, true, &[("bob", "Bob"), ("carol", "Carol")]),
+            game(1, false, &[("bob", "Bob")])
+        ];
+        let linked_accounts = vec![account("42", "carol"), account("7", "someone else")];
+
+        assert_eq!(
+            create_duo_partners_string(&find_duo_partners(&games), &linked_accounts),
+            "Played with: Bob#EUW 3 games, 66% winrate; Carol#EUW (<@42>) 2 games, 100% winrate\n"
+        );
+        assert_eq!(create_duo_partners_string(&Vec::new(), &linked_accounts), "");
+    }
+}
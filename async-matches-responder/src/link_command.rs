@@ -0,0 +1,22 @@
+use lol;
+use common::discord_bot_types;
+use common::storage::BotStorage;
+use crate::lol_command;
+use crate::models;
+
+pub async fn execute_link_command(
+    lol_api_fetcher: &lol::api_fetcher::BoundedHttpFetcher,
+    storage: &dyn BotStorage,
+    command: &discord_bot_types::LinkCommand) -> Result<String, discord_bot_types::BotError> {
+
+    let api_key = lol_command::get_api_key()?;
+    let puuid = lol::get_puuid(lol_api_fetcher, "euw1", &command.player_name, &api_key).await.map_err(models::to_bot_error)?;
+
+    common::linked_accounts::link_account(storage, &command.guild_id, &command.discord_user_id, &puuid, &command.player_name).await
+        .map_err(|err| discord_bot_types::BotError {
+            statusCode: 500,
+            body: format!("Could not link account: {}", err)
+        })?;
+
+    return Ok(format!("Linked your account to {}. Use /unlink to undo this.", command.player_name));
+}
@@ -0,0 +1,112 @@
+use lol;
+use common::discord_bot_types;
+use crate::lol_command;
+use crate::models;
+use futures::future::join_all;
+use std::collections::HashMap;
+
+const BLUE_TEAM_ID: u64 = 100;
+const RED_TEAM_ID: u64 = 200;
+
+pub async fn execute_live_command(
+    lol_api_fetcher: &lol::api_fetcher::BoundedHttpFetcher,
+    command: &discord_bot_types::LiveCommand) -> Result<String, discord_bot_types::BotError> {
+
+    let api_key = lol_command::get_api_key()?;
+    let puuid = lol::get_puuid(lol_api_fetcher, "euw1", &command.player_name, &api_key).await.map_err(models::to_bot_error)?;
+    let active_game = lol::get_active_game(lol_api_fetcher, "euw1", &puuid, &api_key).await.map_err(models::to_bot_error)?;
+
+    let game = match active_game {
+        None => return Ok(format!("{} is not in a game right now.", command.player_name)),
+        Some(game) => game
+    };
+
+    // The names are only for display, so carry on with IDs if the static data is unavailable
+    let static_data = lol::static_data::get_static_data().await
+        .map_err(|err| println!("Could not get static data: {:?}", err))
+        .ok();
+
+    let ranks = get_solo_queue_ranks(lol_api_fetcher, &api_key, &game.participants).await;
+
+    let queue = match (game.gameQueueConfigId, static_data) {
+        (Some(queue_id), Some(static_data)) => static_data.queue_name(queue_id),
+        _ => game.gameMode.to_string()
+    };
+    let mut message = format!("**{}** is in a {} game ({})\n", command.player_name, queue, create_elapsed_time_string(&game));
+
+    for (team_id, team_name) in [(BLUE_TEAM_ID, "🔵 Blue team"), (RED_TEAM_ID, "🔴 Red team")] {
+        message.push_str(&format!("{}\n", team_name));
+
+        for participant in game.participants.iter().filter(|participant| participant.teamId == team_id) {
+            let is_requested_player = participant.puuid.as_deref() == Some(puuid.as_str());
+            message.push_str(&create_participant_string(participant, is_requested_player, static_data, &ranks));
+            message.push_str("\n");
+        }
+    }
+
+    return Ok(message);
+}
+
+/**
+ * Looks up the solo queue rank of every (non bot) participant, keyed by summoner ID. Players whose rank we
+ * couldn't get are left out.
+ */
+async fn get_solo_queue_ranks(
+    lol_api_fetcher: &lol::api_fetcher::BoundedHttpFetcher,
+    api_key: &str,
+    participants: &Vec<lol::models::CurrentGameParticipant>) -> HashMap<String, Option<lol::models::LeagueEntry>> {
+
+    let summoner_ids: Vec<String> = participants.iter()
+        .filter(|participant| !participant.bot)
+        .filter_map(|participant| participant.summonerId.clone())
+        .collect();
+
+    let futures = summoner_ids.iter().map(|summoner_id| lol::get_solo_queue_ranking(lol_api_fetcher, "euw1", summoner_id.to_string(), api_key));
+    let results = join_all(futures).await;
+
+    return summoner_ids.into_iter()
+        .zip(results.into_iter())
+        .filter_map(|(summoner_id, result)| result.ok().map(|rank| (summoner_id, rank)))
+        .collect();
+}
+
+fn create_participant_string(
+    participant: &lol::models::CurrentGameParticipant,
+    is_requested_player: bool,
+    static_data: Option<&lol::static_data::StaticData>,
+    ranks: &HashMap<String, Option<lol::models::LeagueEntry>>) -> String {
+
+    let champion = static_data.map(|data| data.champion_name(participant.championId)).unwrap_or(format!("Champion {}", participant.championId));
+    let spell_1 = static_data.map(|data| data.summoner_spell_name(participant.spell1Id)).unwrap_or(format!("Spell {}", participant.spell1Id));
+    let spell_2 = static_data.map(|data| data.summoner_spell_name(participant.spell2Id)).unwrap_or(format!("Spell {}", participant.spell2Id));
+
+    let name = if participant.bot {"Bot".to_string()} else {participant.riotId.clone().unwrap_or("Unknown player".to_string())};
+    let name = if is_requested_player {format!("**{}**", name)} else {name};
+
+    let rank = participant.summonerId.as_ref()
+        .and_then(|summoner_id| ranks.get(summoner_id))
+        .map(|rank| match rank {
+            None => "Unranked".to_string(),
+            Some(entry) => format!("{} {} LP", lol::rank::format_rank(entry), entry.leaguePoints)
+        })
+        .unwrap_or("Rank unknown".to_string());
+
+    return format!("[{}] {} ({}/{}) - {}", champion, name, spell_1, spell_2, rank);
+}
+
+fn create_elapsed_time_string(game: &lol::models::CurrentGameInfo) -> String {
+    let now_millis = common::date_range::now_millis().unwrap_or(0);
+
+    let elapsed_seconds = if game.gameStartTime == 0 {
+        // Still loading in
+        0
+    } else {
+        now_millis.saturating_sub(game.gameStartTime) / 1000
+    };
+
+    if elapsed_seconds == 0 {
+        return "loading in".to_string();
+    }
+
+    return format!("{}:{:02} elapsed", elapsed_seconds / 60, elapsed_seconds % 60);
+}
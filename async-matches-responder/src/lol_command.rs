@@ -2,78 +2,265 @@ use lol;
 use std::env;
 use common::discord_bot_types;
 use crate::models;
+use crate::progress::ProgressReporter;
+use crate::rank_command;
+use crate::stats;
+use crate::duos;
+use common::storage::BotStorage;
+use std::collections::HashMap;
+use futures::StreamExt;
+use tokio::time::{timeout_at, Instant};
+
+/**
+ * Only the most played champions are listed so the table stays readable
+ */
+const MAX_CHAMPION_TABLE_ROWS: usize = 10;
 
 pub async fn execute_played_command(
     lol_api_fetcher: &lol::api_fetcher::BoundedHttpFetcher,
-    command: &discord_bot_types::PlayedCommand
+    storage: &dyn BotStorage,
+    command: &discord_bot_types::PlayedCommand,
+    deadline: Instant,
+    progress: &ProgressReporter
     ) -> Result<String, discord_bot_types::BotError> {
 
-    let days = if command.days > 7 {7} else { command.days };
+    // Commands queued before ranges were supported only have the number of days
+    let requested_range = command.range.clone().unwrap_or_else(|| {
+        common::date_range::last_days(command.days, common::date_range::now_seconds().unwrap_or(0))
+    });
+
+    let (range, truncated) = common::date_range::clamp_to_match_history(&requested_range);
+    let period = models::SummaryPeriod {
+        range: range,
+        truncated: truncated
+    };
 
     let api_key: String = env::var("LOL_API_KEY").map_err(|err| discord_bot_types::BotError {
         statusCode: 500,
         body: "Missing LOL API key".to_string()
     })?;
 
+    let champion = match &command.champion {
+        None => None,
+        Some(champion_name) => {
+            let static_data = lol::static_data::get_static_data().await.map_err(models::to_bot_error)?;
+
+            match static_data.find_champion(champion_name) {
+                None => return Ok(format!("There is no champion called {}.", champion_name)),
+                Some(champion) => Some(champion.clone())
+            }
+        }
+    };
+
+    let filter = models::GameFilter {
+        queue: command.queue,
+        champion: champion,
+        role: command.role.clone()
+    };
+
+    // Failing to read the linked accounts only means partners can't be highlighted
+    let linked_accounts = match &command.guild_id {
+        None => Vec::new(),
+        Some(guild_id) => common::linked_accounts::get_linked_accounts(storage, guild_id).await.unwrap_or(Vec::new())
+    };
+
+    let options = models::SummaryOptions {
+        period: period,
+        filter: filter,
+        detail: command.detail,
+        linked_accounts: linked_accounts
+    };
+
     match &command.game_type {
-        Some(mode) if mode == "ranked" => get_ranked_games_summary(lol_api_fetcher, &command.player_name, days).await,
-        None => get_all_games_summary(lol_api_fetcher, &command.player_name, days).await,
+        Some(mode) if mode == "ranked" => get_ranked_games_summary(lol_api_fetcher, storage, &command.player_name, &options, deadline, progress).await,
+        None => get_all_games_summary(lol_api_fetcher, &command.player_name, &options, deadline, progress).await,
         Some(mode) => Ok(format!("Unrecognised game mode {}", mode))
     }
 }
 
-pub async fn get_ranked_games_summary(lol_api_fetcher: &lol::api_fetcher::BoundedHttpFetcher, player_name: &str, days: u64) -> Result<String, discord_bot_types::BotError> {
+pub async fn get_ranked_games_summary(
+    lol_api_fetcher: &lol::api_fetcher::BoundedHttpFetcher,
+    storage: &dyn BotStorage,
+    player_name: &str,
+    options: &models::SummaryOptions,
+    deadline: Instant,
+    progress: &ProgressReporter) -> Result<String, discord_bot_types::BotError> {
     let api_key = get_api_key()?;
-    let summary = get_games_over_time(lol_api_fetcher, &api_key, player_name, days, Some("ranked".to_string()));
-    let ranked_sum = get_current_rank(lol_api_fetcher, &api_key, player_name);
+    let summary = get_games_over_time(lol_api_fetcher, &api_key, player_name, &options.period.range, Some("ranked".to_string()), &options.filter, deadline, progress);
+    let ranked_sum = async {
+        let rank = rank_command::fetch_and_record_rankings(lol_api_fetcher, storage, &api_key, player_name, options.period.range.start).await?
+            .into_iter()
+            .find(|ranking| ranking.entry.queueType == "RANKED_SOLO_5x5");
+
+        if let Some(ranking) = &rank {
+            progress.rank_found(format!("{} is {}", player_name, rank_command::create_rank_with_change_string(ranking)));
+        }
+
+        Ok::<_, discord_bot_types::BotError>(rank)
+    };
 
     let (game_summaries, ranked_summary) = tokio::try_join!(summary, ranked_sum)?;
-    let time_played_string: String = create_time_played_string(game_summaries.played_for_millis);
+    let time_played_string: String = common::date_range::format_duration(game_summaries.played_for_millis);
 
     match ranked_summary {
         None => Ok(format!("{} has not played any ranked games.", player_name)),
         Some(ranked_summary) => {
-            let mut message = format!("{} ({} {}, {} points) has played for {} over {} days\nThey won {} games and lost {}", player_name, ranked_summary.tier, ranked_summary.rank, ranked_summary.leaguePoints, time_played_string, days, game_summaries.wins, game_summaries.losses).to_string();
+            let mut message = format!("{} is {} and has played for {} {}\nThey won {} games and lost {}", player_name, rank_command::create_rank_with_change_string(&ranked_summary), time_played_string, options.period.range.description, game_summaries.wins, game_summaries.losses).to_string();
             message.push_str("\n");
-            message.push_str(&create_summaries_string(game_summaries.games));
+            message.push_str(&create_summary_body_string(game_summaries, options).await);
         
             return Ok(message);
         }
     }
 }
 
-pub async fn get_all_games_summary(lol_api_fetcher: &lol::api_fetcher::BoundedHttpFetcher, player_name: &str, days: u64) -> Result<String, discord_bot_types::BotError> {
+pub async fn get_all_games_summary(lol_api_fetcher: &lol::api_fetcher::BoundedHttpFetcher, player_name: &str, options: &models::SummaryOptions, deadline: Instant, progress: &ProgressReporter) -> Result<String, discord_bot_types::BotError> {
     let api_key = get_api_key()?;
-    let game_summaries = get_games_over_time(lol_api_fetcher, &api_key, player_name, days, None).await?;
-    let time_played_string: String = create_time_played_string(game_summaries.played_for_millis);
+    let game_summaries = get_games_over_time(lol_api_fetcher, &api_key, player_name, &options.period.range, None, &options.filter, deadline, progress).await?;
+    let time_played_string: String = common::date_range::format_duration(game_summaries.played_for_millis);
 
-    let mut message = format!("{} has played for {} over {} days\nThey won {} games and lost {}", player_name, time_played_string, days, game_summaries.wins, game_summaries.losses).to_string();
+    let mut message = format!("{} has played for {} {}\nThey won {} games and lost {}", player_name, time_played_string, options.period.range.description, game_summaries.wins, game_summaries.losses).to_string();
     message.push_str("\n");
-    message.push_str(&create_summaries_string(game_summaries.games));
+    message.push_str(&create_summary_body_string(game_summaries, options).await);
 
     return Ok(message);
 }
 
-fn create_summaries_string(summaries: Vec<lol::models::UserGameSummary>) -> String {
+/**
+ * Everything in a summary after the headline: notes on what it covers, the champion table, duo partners and the
+ * most recent games
+ */
+async fn create_summary_body_string(game_summaries: models::GamesOverTimeSummary, options: &models::SummaryOptions) -> String {
     let mut result = "".to_string();
-    for summary in summaries.iter().take(10) {
-        let game_summary_string = create_game_stats_string(&summary);
-        result.push_str(&game_summary_string);
-        result.push_str("\n");
+    result.push_str(&create_filter_string(&options.filter).await);
+    result.push_str(&create_truncated_range_string(options.period.truncated));
+    result.push_str(&create_partial_results_string(game_summaries.fetched_games, game_summaries.total_games));
+    result.push_str(&create_champion_table_string(&game_summaries.champions));
+
+    // Streaks are as of the player's latest game, which a filtered summary might not include
+    if options.filter.is_empty() {
+        result.push_str(&create_streaks_string(&game_summaries.games));
     }
+
+    result.push_str(&duos::create_duo_partners_string(&duos::find_duo_partners(&game_summaries.games), &options.linked_accounts));
+    result.push_str(&create_summaries_string(game_summaries.games, options.detail));
+
     return result;
 }
 
-pub async fn get_current_rank(
-    lol_api_fetcher: &lol::api_fetcher::BoundedHttpFetcher,
-    api_key: &str,
-    player_name: &str) -> Result<Option<lol::models::LeagueEntry>, discord_bot_types::BotError> {
+fn create_streaks_string(games: &Vec<lol::models::UserGameSummary>) -> String {
+    return match lol::streaks::describe_streaks(games) {
+        None => "".to_string(),
+        Some(streaks) => format!("{}\n", streaks)
+    };
+}
+
+/**
+ * e.g. 'Only counting Ranked Solo/Duo games on Ahri as Mid'
+ */
+async fn create_filter_string(filter: &models::GameFilter) -> String {
+    if filter.is_empty() {
+        return "".to_string();
+    }
+
+    let mut result = "Only counting".to_string();
+
+    match filter.queue {
+        None => result.push_str(" games"),
+        Some(queue_id) => {
+            let queue_name = match lol::static_data::get_static_data().await {
+                Ok(static_data) => static_data.queue_name(queue_id),
+                Err(_) => format!("queue {}", queue_id)
+            };
+
+            result.push_str(&format!(" {} games", queue_name));
+        }
+    }
+
+    if let Some(champion) = &filter.champion {
+        result.push_str(&format!(" on {}", champion.name));
+    }
+
+    if let Some(role) = &filter.role {
+        result.push_str(&format!(" as {}", role_display_name(role)));
+    }
 
-    let summoner_id = lol::get_encrypted_summoner_id(lol_api_fetcher, "euw1", player_name, &api_key).await.map_err(models::to_bot_error)?;
-    return lol::get_solo_queue_ranking(lol_api_fetcher, "euw1", summoner_id, &api_key).await.map_err(models::to_bot_error);
+    result.push_str("\n");
+    return result;
+}
+
+fn role_display_name(role: &str) -> String {
+    let name = match role {
+        "TOP" => "Top",
+        "JUNGLE" => "Jungle",
+        "MIDDLE" => "Mid",
+        "BOTTOM" => "Bot",
+        "UTILITY" => "Support",
+        other => return other.to_string()
+    };
+
+    return name.to_string();
+}
+
+pub(crate) fn create_truncated_range_string(truncated: bool) -> String {
+    if truncated {
+        return format!("(Riot only keep match history from {}, so this starts from then)\n", common::date_range::format_timestamp(lol::MATCH_HISTORY_START));
+    } else {
+        return "".to_string();
+    }
+}
+
+pub(crate) fn create_partial_results_string(fetched_games: usize, total_games: usize) -> String {
+    if fetched_games < total_games {
+        return format!("(I ran out of time after fetching {} of {} games, so this only covers those)\n", fetched_games, total_games);
+    } else {
+        return "".to_string();
+    }
 }
 
-fn get_api_key() -> Result<String, discord_bot_types::BotError> {
+/**
+ * A table of how the player did on each champion, e.g.
+ *
+ * Champion      Games  Win%  KDA   CS/min
+ * Ahri              5   60%  3.20    7.1
+ */
+fn create_champion_table_string(champions: &Vec<models::ChampionSummary>) -> String {
+    if champions.is_empty() {
+        return "".to_string();
+    }
+
+    let mut result = "```\n".to_string();
+    result.push_str(&format!("{:<14}{:>5}{:>6}{:>6}{:>8}\n", "Champion", "Games", "Win%", "KDA", "CS/min"));
+
+    for champion in champions.iter().take(MAX_CHAMPION_TABLE_ROWS) {
+        let kda = lol::stats::kda(champion.kills, champion.deaths, champion.assists);
+        let creep_score_per_minute = lol::stats::creep_score_per_minute(champion.creep_score, champion.played_for_millis);
+
+        result.push_str(&format!(
+            "{:<14}{:>5}{:>5}%{:>6.2}{:>8.1}\n",
+            champion.champion_name.chars().take(13).collect::<String>(),
+            champion.games,
+            champion.wins * 100 / champion.games,
+            kda,
+            creep_score_per_minute
+        ));
+    }
+
+    result.push_str("```\n");
+    return result;
+}
+
+fn create_summaries_string(summaries: Vec<lol::models::UserGameSummary>, detail: discord_bot_types::DetailLevel) -> String {
+    let mut result = "".to_string();
+    for summary in summaries.iter().take(10) {
+        let game_summary_string = create_game_stats_string(&summary, detail);
+        result.push_str(&game_summary_string);
+        result.push_str("\n");
+    }
+    return result;
+}
+
+pub fn get_api_key() -> Result<String, discord_bot_types::BotError> {
     return env::var("LOL_API_KEY").map_err(|err| discord_bot_types::BotError {
         statusCode: 500,
         body: "Missing LOL API key".to_string()
@@ -84,8 +271,11 @@ async fn get_games_over_time(
     lol_api_fetcher: &lol::api_fetcher::BoundedHttpFetcher,
     api_key: &str,
     player_name: &str,
-    days: u64,
-    game_type: Option<String>) ->  Result<models::GamesOverTimeSummary, discord_bot_types::BotError> {
+    range: &common::date_range::DateRange,
+    game_type: Option<String>,
+    filter: &models::GameFilter,
+    deadline: Instant,
+    progress: &ProgressReporter) ->  Result<models::GamesOverTimeSummary, discord_bot_types::BotError> {
     
     println!("Executing get_games_over_time");
 
@@ -95,50 +285,106 @@ async fn get_games_over_time(
     })?;
 
     let puuid = lol::get_puuid(&lol_api_fetcher, "euw1", player_name, &api_key).await.map_err(models::to_bot_error)?;
-    let game_ids = lol::get_game_ids(&lol_api_fetcher, &api_key, "europe", &puuid, days, &game_type).await.map_err(models::to_bot_error)?;
-    let models = lol::fetch_game_summaries(&lol_api_fetcher, &api_key, "europe", &puuid, game_ids).await.map_err(models::to_bot_error)?;
+    let game_ids = lol::get_game_ids(&lol_api_fetcher, &api_key, "europe", &puuid, range, &lol::models::GameIdsFilter {
+        game_type: game_type,
+        queue: filter.queue
+    }).await.map_err(models::to_bot_error)?;
+    let total_games = game_ids.len();
+    progress.games_found(total_games);
+
+    let mut summaries_stream = lol::stream_game_summaries(&lol_api_fetcher, &api_key, "europe", &puuid, game_ids);
+    let mut models: Vec<lol::models::UserGameSummary> = Vec::new();
+    let mut fetched_games: usize = 0;
+
+    // Summarise whatever we managed to fetch before the deadline rather than leaving the user with nothing
+    loop {
+        match timeout_at(deadline, summaries_stream.next()).await {
+            Ok(Some(summary)) => {
+                let summary = summary.map_err(models::to_bot_error)?;
+                fetched_games = fetched_games + 1;
+                progress.game_fetched();
+
+                if filter.matches(&summary.participant) {
+                    models.push(summary);
+                }
+            },
+            Ok(None) => break,
+            Err(_) => {
+                println!("Deadline reached after fetching {} of {} games", fetched_games, total_games);
+                break;
+            }
+        }
+    }
+
+    // The summaries arrive in whatever order the requests complete
+    models.sort_by(|a, b| b.game_end_timestamp.cmp(&a.game_end_timestamp).then(b.game_id.cmp(&a.game_id)));
 
     let played_for: u64 = calculate_time_played(&models);
     let wins = calculate_wins(&models);
     let loses = calculate_loses(&models);
+    let champions = calculate_champion_summaries(&models);
 
     let result = models::GamesOverTimeSummary {
+        champions: champions,
         games: models,
         wins: wins,
         losses: loses,
-        played_for_millis: played_for 
+        played_for_millis: played_for,
+        total_games: total_games,
+        fetched_games: fetched_games
     };
     
     Ok(result)
 }
 
-fn calculate_time_played(summaries: &Vec<lol::models::UserGameSummary>) -> u64 {
+pub fn calculate_time_played(summaries: &Vec<lol::models::UserGameSummary>) -> u64 {
     return summaries.iter().map(|x| x.game_duration_millis).sum();
 }
 
-fn calculate_wins(summaries: &Vec<lol::models::UserGameSummary>) -> u64 {
+pub fn calculate_wins(summaries: &Vec<lol::models::UserGameSummary>) -> u64 {
     return summaries.iter().map(|x| if x.participant.win == true {1} else {0}).sum();
 }
 
-fn calculate_loses(summaries: &Vec<lol::models::UserGameSummary>) -> u64 {
+pub fn calculate_loses(summaries: &Vec<lol::models::UserGameSummary>) -> u64 {
     return summaries.iter().map(|x| if x.participant.win == true {0} else {1}).sum();
 }
 
-fn create_time_played_string(millis: u64) -> String {
-    println!("millis: {}", millis);
-    let seconds = millis / 1000;
-    let minutes = seconds / 60;
-    let hours = minutes / 60;
-    
-    let minutes = minutes % 60;
-    return format!("{} hours and {} minutes", hours, minutes);
+pub fn calculate_champion_summaries(summaries: &Vec<lol::models::UserGameSummary>) -> Vec<models::ChampionSummary> {
+    let mut champions: HashMap<String, models::ChampionSummary> = HashMap::new();
+
+    for summary in summaries.iter() {
+        let participant = &summary.participant;
+        let champion = champions.entry(participant.championName.to_string()).or_insert(models::ChampionSummary {
+            champion_name: participant.championName.to_string(),
+            games: 0,
+            wins: 0,
+            kills: 0,
+            deaths: 0,
+            assists: 0,
+            creep_score: 0,
+            played_for_millis: 0
+        });
+
+        champion.games += 1;
+        champion.wins += if participant.win {1} else {0};
+        champion.kills += participant.kills;
+        champion.deaths += participant.deaths;
+        champion.assists += participant.assists;
+        champion.creep_score += participant.totalMinionsKilled + participant.neutralMinionsKilled;
+        champion.played_for_millis += summary.game_duration_millis;
+    }
+
+    let mut result: Vec<models::ChampionSummary> = champions.into_values().collect();
+    result.sort_by(|a, b| b.games.cmp(&a.games).then(b.wins.cmp(&a.wins)).then(a.champion_name.cmp(&b.champion_name)));
+
+    return result;
 }
 
-fn create_game_stats_string(game_summary: &lol::models::UserGameSummary) -> String {
+fn create_game_stats_string(game_summary: &lol::models::UserGameSummary, detail: discord_bot_types::DetailLevel) -> String {
     let participant = &game_summary.participant;
     let full_info_url = format!("https://www.leagueofgraphs.com/match/euw/{}#participant1", game_summary.game_id);
 
     let win_or_loss = if game_summary.participant.win {"Win"} else {"Loss"};
 
-    return format!("[{}] {}/{}/{} ({}) <{}>", participant.championName, participant.kills, participant.deaths, participant.assists, win_or_loss, full_info_url);
+    return format!("[{}] {}/{}/{} ({}){} <{}>", participant.championName, participant.kills, participant.deaths, participant.assists, win_or_loss, stats::create_detail_string(game_summary, detail), full_info_url);
 }
@@ -1,25 +1,51 @@
 use lambda_runtime::{service_fn, LambdaEvent, Error};
 use serde_json::{json, Value};
-use aws_config::meta::region::RegionProviderChain;
-use aws_sdk_dynamodb::{Client};
+use common::discord_rest::{DiscordRestClient, MessagePayload};
+use common::discord_bot_types::QueuedCommand;
+use common::storage::BotStorage;
+use futures::FutureExt;
+use std::env;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::{timeout_at, Instant};
 
 use lol;
 use common;
 mod models;
 mod lol_command;
+mod progress;
+mod messages;
+mod rank_command;
+mod live_command;
+mod mastery_command;
+mod stats;
+mod compare_command;
+mod duos;
+mod link_command;
+mod track_command;
+mod budget_command;
+
+/**
+ * How long before the Lambda deadline we stop fetching games and summarise what we have so far
+ */
+const SUMMARY_DEADLINE_MARGIN: Duration = Duration::from_secs(10);
+
+/**
+ * How long before the Lambda deadline we give up on a command entirely and tell the user it timed out
+ */
+const ABANDON_DEADLINE_MARGIN: Duration = Duration::from_secs(3);
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
-    let region_provider = RegionProviderChain::default_provider().or_else("us-east-1");
-    let config = aws_config::from_env().region(region_provider).load().await;
-    let dynamo_client = Client::new(&config);
+    let storage = common::storage::create_storage(common::storage::storage_config_from_env()?).await?;
 
     let api_fetcher: lol::api_fetcher::BoundedHttpFetcher = lol::api_fetcher::create_lol_client(20,100);
-    let client = reqwest::Client::new();
     let toolbox = models::Toolbox {
         lol_api_fetcher: api_fetcher,
-        discord_http_client: client,
-        dynamo_client: dynamo_client
+        discord_client: common::discord_rest::create_discord_client_from_env(),
+        storage: storage
     };
 
     let func = service_fn(|x| func(&toolbox, x));
@@ -28,21 +54,42 @@ async fn main() -> Result<(), Error> {
 }
 
 async fn func(toolbox: &models::Toolbox, lambda_event: LambdaEvent<models::QueueBatch>) -> Result<Value, serde_json::Error> {
-    let (event, _context) = lambda_event.into_parts();
+    let (event, context) = lambda_event.into_parts();
     println!("Received: {:?}", event);
 
+    // Messages end up on the dead letter queue when we crashed or timed out without replying, so we just apologise
+    let dead_letter_mode = env::var("RESPONDER_MODE").map(|mode| mode == "dead_letter").unwrap_or(false);
+    let lambda_deadline = to_instant(context.deadline);
+
     for record in event.Records.iter() {
-        let request: Result<common::discord_bot_types::PlayedCommand, _> = serde_json::from_str(&record.body);
+        let request = QueuedCommand::from_json(&record.body);
 
         match request {
-            Err(err) => println!("Could not parse played command to type"),
+            Err(err) => {
+                println!("Could not parse queued command to type: {}", err);
+                reply_to_unreadable_command(&toolbox.discord_client, &record.body).await;
+            },
+            Ok(command) if dead_letter_mode => {
+                println!("Command was abandoned: {}", record.body);
+                send_message(&toolbox.discord_client, &command, "Sorry, something went wrong and I had to give up on that one. Please try again later.").await;
+            },
             Ok(command) => { 
-                let result = handle_played_command(
-                    &toolbox.lol_api_fetcher, &toolbox.discord_http_client,
-                    &toolbox.dynamo_client,
-                    &command
-                ).await;
-                println!("Result: {:?}", result);
+                let summary_deadline = lambda_deadline.checked_sub(SUMMARY_DEADLINE_MARGIN).unwrap_or(lambda_deadline);
+                let abandon_deadline = lambda_deadline.checked_sub(ABANDON_DEADLINE_MARGIN).unwrap_or(lambda_deadline);
+
+                let handler = AssertUnwindSafe(handle_command(toolbox, &command, summary_deadline)).catch_unwind();
+
+                match timeout_at(abandon_deadline, handler).await {
+                    Ok(Ok(result)) => println!("Result: {:?}", result),
+                    Ok(Err(_panic)) => {
+                        println!("Handling command panicked");
+                        send_message(&toolbox.discord_client, &command, "Sorry, something went wrong while I was working on that.").await;
+                    },
+                    Err(_elapsed) => {
+                        println!("Timed out handling command");
+                        send_message(&toolbox.discord_client, &command, "Sorry, that took too long. Try asking for fewer days.").await;
+                    }
+                }
             }
         }
     }
@@ -50,13 +97,49 @@ async fn func(toolbox: &models::Toolbox, lambda_event: LambdaEvent<models::Queue
     return Ok(json!({}));
 }
 
-async fn handle_played_command(
-    lol_api_fetcher: &lol::api_fetcher::BoundedHttpFetcher,
-    discord_http_client: &reqwest:: Client,
-    dynamo_client: &Client,
-    command: &common::discord_bot_types::PlayedCommand) -> Result<(), common::discord_bot_types::BotError> {
+/**
+ * Replaces the 'thinking...' message with an apology when we can't make sense of the queued command, as long as
+ * we can at least find out which interaction it was for
+ */
+async fn reply_to_unreadable_command(discord_client: &DiscordRestClient, body: &str) {
+    let value: Value = match serde_json::from_str(body) {
+        Ok(value) => value,
+        Err(_) => return
+    };
+
+    if let (Some(application_id), Some(token)) = (value["application_id"].as_str(), value["token"].as_str()) {
+        let payload = MessagePayload::from_content("Sorry, something went wrong and I couldn't read that command. Please try again.");
+        let send_result = discord_client.edit_original_response(application_id, token, &payload).await;
+
+        println!("Discord send result: {:?}", send_result);
+    }
+}
 
-    let result = lol_command::execute_played_command(lol_api_fetcher, command).await;
+/**
+ * Converts the Lambda deadline (in milliseconds since the epoch) to an instant we can use with tokio timers
+ */
+fn to_instant(deadline_epoch_millis: u64) -> Instant {
+    let deadline = UNIX_EPOCH + Duration::from_millis(deadline_epoch_millis);
+    let remaining = deadline.duration_since(SystemTime::now()).unwrap_or(Duration::from_secs(0));
+
+    return Instant::now() + remaining;
+}
+
+async fn handle_command(
+    toolbox: &models::Toolbox,
+    command: &QueuedCommand,
+    deadline: Instant) -> Result<(), common::discord_bot_types::BotError> {
+
+    let result = match command {
+        QueuedCommand::Played(played_command) => handle_played_command(toolbox, played_command, deadline).await,
+        QueuedCommand::Rank(rank_command) => rank_command::execute_rank_command(&toolbox.lol_api_fetcher, toolbox.storage.as_ref(), rank_command).await,
+        QueuedCommand::Live(live_command) => live_command::execute_live_command(&toolbox.lol_api_fetcher, live_command).await,
+        QueuedCommand::Mastery(mastery_command) => mastery_command::execute_mastery_command(&toolbox.lol_api_fetcher, mastery_command).await,
+        QueuedCommand::Compare(compare_command) => handle_compare_command(toolbox, compare_command, deadline).await,
+        QueuedCommand::Link(link_command) => link_command::execute_link_command(&toolbox.lol_api_fetcher, toolbox.storage.as_ref(), link_command).await,
+        QueuedCommand::Track(track_command) => track_command::execute_track_command(&toolbox.lol_api_fetcher, toolbox.storage.as_ref(), track_command).await,
+        QueuedCommand::Budget(budget_command) => budget_command::execute_budget_command(&toolbox.lol_api_fetcher, toolbox.storage.as_ref(), budget_command).await
+    };
 
     let message = match &result {
         Err(bot_error) if bot_error.statusCode == 429 => "Too many requests in a short period of time, try again in a minute.".to_string(),
@@ -68,22 +151,14 @@ async fn handle_played_command(
         }
     };
 
-    let discord_content_body = models::DiscordResponseBody {
-        content: message.to_string()
-    };
-
-    let body = serde_json::to_string(&discord_content_body).map_err(|x| common::discord_bot_types::BotError {
-        statusCode: 500,
-        body: "Could not write type to JSON string".to_string()
-    })?;
+    let response_future = send_message(&toolbox.discord_client, command, &message);
 
-    let response_future = send_response(discord_http_client, body, &command.application_id, &command.token);
+    // Some commands (e.g. clearing a budget) aren't about a player, so there's nothing to remember
+    if result.is_ok() && !command.player_name().is_empty() {
+        let user_count_future = update_user_count(toolbox.storage.as_ref(), command.discord_user_id(), command.player_name());
+        let (x, storage_result) = tokio::join!(response_future, user_count_future);
 
-    if result.is_ok() {
-        let user_count_future = update_user_count(dynamo_client, &command.discord_user_id, &command.player_name);
-        let (x, dynamo_result) = tokio::join!(response_future, user_count_future);
-
-        println!("Dynamo update result: {:?}", dynamo_result);
+        println!("Search history update result: {:?}", storage_result);
     } else {
         response_future.await;
     }
@@ -91,31 +166,88 @@ async fn handle_played_command(
     Ok(())
 }
 
-async fn send_response(discord_http_client: &reqwest:: Client, body: String, application_id: &str, token: &str) {
-    let mut headers = reqwest::header::HeaderMap::new();
-    let value = reqwest::header::HeaderValue::from_static("application/json");
-    headers.insert("Content-Type", value);
+async fn handle_played_command(
+    toolbox: &models::Toolbox,
+    command: &common::discord_bot_types::PlayedCommand,
+    deadline: Instant) -> Result<String, common::discord_bot_types::BotError> {
+
+    return run_with_progress(toolbox, &command.application_id, &command.token, &command.player_name, |progress_reporter| async move {
+        lol_command::execute_played_command(&toolbox.lol_api_fetcher, toolbox.storage.as_ref(), command, deadline, &progress_reporter).await
+    }).await;
+}
 
-    let request_url = format!("https://discord.com/api/webhooks/{}/{}/messages/@original", application_id, token);
-    let send_result = discord_http_client
-        .patch(request_url)
-        .headers(headers)
-        .body(body)
-        .send()
-        .await;
+async fn handle_compare_command(
+    toolbox: &models::Toolbox,
+    command: &common::discord_bot_types::CompareCommand,
+    deadline: Instant) -> Result<String, common::discord_bot_types::BotError> {
+
+    let players = format!("{} and {}", command.player_name, command.other_player_name);
+
+    return run_with_progress(toolbox, &command.application_id, &command.token, &players, |progress_reporter| async move {
+        compare_command::execute_compare_command(&toolbox.lol_api_fetcher, toolbox.storage.as_ref(), command, deadline, &progress_reporter).await
+    }).await;
+}
+
+/**
+ * Runs the command while editing its progress into the deferred message. The reporter is dropped along with the
+ * command's future, and we wait for any progress edit in flight to go out so that it can't overwrite the final
+ * message.
+ */
+async fn run_with_progress<F, Fut>(
+    toolbox: &models::Toolbox,
+    application_id: &str,
+    token: &str,
+    player_name: &str,
+    command: F) -> Result<String, common::discord_bot_types::BotError>
+where
+    F: FnOnce(Arc<progress::ProgressReporter>) -> Fut,
+    Fut: Future<Output = Result<String, common::discord_bot_types::BotError>> {
+
+    let (progress_reporter, progress_task) = progress::start_progress_reporter(
+        toolbox.discord_client.clone(),
+        application_id.to_string(),
+        token.to_string(),
+        player_name
+    );
+
+    // If the command times out or panics, this future is dropped along with the progress task, which stops it
+    let result = command(Arc::new(progress_reporter)).await;
+
+    let progress_result = progress_task.finish().await;
+    println!("Progress reporter result: {:?}", progress_result);
+
+    return result;
+}
+
+/**
+ * Replaces the deferred 'thinking...' message for the command with the given message. If the message is too long
+ * for one Discord message, the rest of it is sent in follow up messages.
+ */
+async fn send_message(discord_client: &DiscordRestClient, command: &QueuedCommand, message: &str) {
+    let parts = messages::split_message(message, messages::DISCORD_MESSAGE_LIMIT);
+    let mut parts_iterator = parts.iter();
+
+    if let Some(first_part) = parts_iterator.next() {
+        let send_result = discord_client
+            .edit_original_response(command.application_id(), command.token(), &MessagePayload::from_content(first_part))
+            .await;
+
+        println!("Discord send result: {:?}", send_result);
+    }
 
-    println!("Discord send result: {:?}", send_result);
+    for part in parts_iterator {
+        // Follow ups aren't private just because the original response was
+        let mut payload = MessagePayload::from_content(part);
+        payload.flags = if command.is_private() {Some(common::discord_bot_types::EPHEMERAL_FLAG)} else {None};
 
-    let result_text = send_result.map( move |x| {x.text()} );
+        let send_result = discord_client.create_followup_message(command.application_id(), command.token(), &payload).await;
 
-    match result_text {
-        Ok(txt) => println!("{:?}", txt.await),
-        Err(err) => println!("{:?}", err)
+        println!("Discord follow up send result: {:?}", send_result);
     }
 }
 
-async fn update_user_count(dynamo_client: &Client, discord_user_id: &str, searched_for: &str) -> Result<(), aws_sdk_dynamodb::Error> {
-    let x = common::search_history::store_search(dynamo_client, discord_user_id, searched_for);
+async fn update_user_count(storage: &dyn BotStorage, discord_user_id: &str, searched_for: &str) -> Result<(), common::storage::StorageError> {
+    let x = common::search_history::store_search(storage, discord_user_id, searched_for);
 
     return x.await;
 }
\ No newline at end of file
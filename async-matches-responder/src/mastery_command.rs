@@ -0,0 +1,108 @@
+use lol;
+use common::discord_bot_types;
+use crate::lol_command;
+use crate::models;
+
+const DEFAULT_MASTERY_COUNT: u64 = 10;
+const MAX_MASTERY_COUNT: u64 = 25;
+
+pub async fn execute_mastery_command(
+    lol_api_fetcher: &lol::api_fetcher::BoundedHttpFetcher,
+    command: &discord_bot_types::MasteryCommand) -> Result<String, discord_bot_types::BotError> {
+
+    let api_key = lol_command::get_api_key()?;
+    let puuid = lol::get_puuid(lol_api_fetcher, "euw1", &command.player_name, &api_key).await.map_err(models::to_bot_error)?;
+    let static_data = lol::static_data::get_static_data().await.map_err(models::to_bot_error)?;
+
+    return match &command.champion {
+        Some(champion) => get_champion_mastery_summary(lol_api_fetcher, &api_key, &puuid, &command.player_name, champion, static_data).await,
+        None => {
+            let count = command.count.unwrap_or(DEFAULT_MASTERY_COUNT).clamp(1, MAX_MASTERY_COUNT);
+            get_top_masteries_summary(lol_api_fetcher, &api_key, &puuid, &command.player_name, count, static_data).await
+        }
+    };
+}
+
+async fn get_top_masteries_summary(
+    lol_api_fetcher: &lol::api_fetcher::BoundedHttpFetcher,
+    api_key: &str,
+    puuid: &str,
+    player_name: &str,
+    count: u64,
+    static_data: &lol::static_data::StaticData) -> Result<String, discord_bot_types::BotError> {
+
+    let masteries = lol::get_champion_masteries(lol_api_fetcher, "euw1", puuid, api_key).await.map_err(models::to_bot_error)?;
+
+    if masteries.is_empty() {
+        return Ok(format!("{} has no champion mastery yet.", player_name));
+    }
+
+    let mut message = format!("**{}**'s top champions by mastery\n", player_name);
+
+    for (position, mastery) in masteries.iter().take(count as usize).enumerate() {
+        message.push_str(&format!("{}. {}\n", position + 1, create_mastery_string(mastery, static_data)));
+    }
+
+    return Ok(message);
+}
+
+async fn get_champion_mastery_summary(
+    lol_api_fetcher: &lol::api_fetcher::BoundedHttpFetcher,
+    api_key: &str,
+    puuid: &str,
+    player_name: &str,
+    champion: &str,
+    static_data: &lol::static_data::StaticData) -> Result<String, discord_bot_types::BotError> {
+
+    let champion = match static_data.find_champion(champion) {
+        None => return Ok(format!("There is no champion called {}.", champion)),
+        Some(champion) => champion
+    };
+
+    let mastery = lol::get_champion_mastery(lol_api_fetcher, "euw1", puuid, champion.id, api_key).await.map_err(models::to_bot_error)?;
+
+    return Ok(match mastery {
+        None => format!("{} has never played {}.", player_name, champion.name),
+        Some(mastery) => {
+            let mut message = format!("**{}**: {}", player_name, create_mastery_string(&mastery, static_data));
+
+            if mastery.championPointsUntilNextLevel > 0 {
+                message.push_str(&format!("\n{} points until level {}", format_points(mastery.championPointsUntilNextLevel), mastery.championLevel + 1));
+            }
+
+            message
+        }
+    });
+}
+
+/**
+ * e.g. 'Ahri - level 7, 254,301 points, last played <t:1650000000:R>'. Discord shows the timestamp in the reader's
+ * own timezone.
+ */
+fn create_mastery_string(mastery: &lol::models::ChampionMastery, static_data: &lol::static_data::StaticData) -> String {
+    return format!(
+        "{} - level {}, {} points, last played <t:{}:R>",
+        static_data.champion_name(mastery.championId),
+        mastery.championLevel,
+        format_points(mastery.championPoints),
+        mastery.lastPlayTime / 1000
+    );
+}
+
+/**
+ * Adds thousands separators, e.g. 254301 becomes '254,301'
+ */
+fn format_points(points: u64) -> String {
+    let digits = points.to_string();
+    let mut result = "".to_string();
+
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index) % 3 == 0 {
+            result.push(',');
+        }
+
+        result.push(digit);
+    }
+
+    return result;
+}
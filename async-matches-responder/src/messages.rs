@@ -0,0 +1,151 @@
+/**
+ * The most characters Discord allows in a message's content
+ */
+pub const DISCORD_MESSAGE_LIMIT: usize = 2000;
+
+/**
+ * Opens and closes a Discord code block
+ */
+const CODE_FENCE: &str = "```";
+
+/**
+ * Splits the message into parts that each fit in a Discord message. Messages are only split between lines so that
+ * a game's row is never cut in half. A single line that is too long by itself is the only thing that gets cut.
+ * A code block that gets split is closed at the end of one part and reopened at the start of the next.
+ */
+pub fn split_message(message: &str, limit: usize) -> Vec<String> {
+    let mut parts: Vec<String> = Vec::new();
+    let mut open_fence: Option<String> = None;
+    let mut part = MessagePart::new(&open_fence);
+
+    for line in message.lines() {
+        let fence_after_line = if !line.trim_start().starts_with(CODE_FENCE) {
+            open_fence.clone()
+        } else if open_fence.is_some() {
+            None
+        } else {
+            Some(line.to_string())
+        };
+        let closing_length = if fence_after_line.is_some() {CODE_FENCE.len() + 1} else {0};
+
+        if part.content_lines > 0 && part.length_with(line.chars().count()) + closing_length > limit {
+            parts.push(part.finish(&open_fence));
+            part = MessagePart::new(&open_fence);
+        }
+
+        let mut remaining: Vec<char> = line.chars().collect();
+
+        while !remaining.is_empty() && part.length_with(remaining.len()) + closing_length > limit {
+            let room = limit.saturating_sub(part.length_with(0) + closing_length).max(1).min(remaining.len());
+            let chunk: String = remaining.drain(..room).collect();
+
+            part.push(&chunk);
+            parts.push(part.finish(&open_fence));
+            part = MessagePart::new(&open_fence);
+        }
+
+        part.push(&remaining.iter().collect::<String>());
+        open_fence = fence_after_line;
+    }
+
+    if part.content_lines > 0 || parts.is_empty() {
+        // A code block the message itself leaves open is left as it is
+        parts.push(part.finish(&None));
+    }
+
+    return parts;
+}
+
+struct MessagePart {
+    lines: Vec<String>,
+    length: usize,
+
+    /**
+     * Lines from the message, not counting a reopened code fence
+     */
+    content_lines: usize
+}
+
+impl MessagePart {
+    /**
+     * Starts a part, reopening the code block the previous part was cut off in
+     */
+    fn new(open_fence: &Option<String>) -> MessagePart {
+        let mut part = MessagePart { lines: Vec::new(), length: 0, content_lines: 0 };
+
+        if let Some(fence) = open_fence {
+            part.push(fence);
+            part.content_lines = 0;
+        }
+
+        return part;
+    }
+
+    fn length_with(&self, line_length: usize) -> usize {
+        let separator_length = if self.lines.is_empty() {0} else {1};
+        return self.length + separator_length + line_length;
+    }
+
+    fn push(&mut self, line: &str) {
+        self.length = self.length_with(line.chars().count());
+        self.lines.push(line.to_string());
+        self.content_lines = self.content_lines + 1;
+    }
+
+    /**
+     * Closes the code block the part was cut off in
+     */
+    fn finish(mut self, open_fence: &Option<String>) -> String {
+        if open_fence.is_some() {
+            self.push(CODE_FENCE);
+        }
+
+        return self.lines.join("\n");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_a_message_exactly_at_the_limit_whole() {
+        let message = "aaaa\nbbbb";
+
+        assert_eq!(split_message(message, 9), vec![message.to_string()]);
+    }
+
+    #[test]
+    fn splits_a_message_one_over_the_limit_between_lines() {
+        let message = "aaaa\nbbbbb";
+
+        assert_eq!(split_message(message, 9), vec!["aaaa".to_string(), "bbbbb".to_string()]);
+    }
+
+    #[test]
+    fn cuts_a_line_too_long_for_a_part_of_its_own() {
+        assert_eq!(split_message("aaaaaaaaaaa", 4), vec!["aaaa".to_string(), "aaaa".to_string(), "aaa".to_string()]);
+    }
+
+    #[test]
+    fn keeps_leading_blank_lines() {
+        assert_eq!(split_message("\n\naaaa", 10), vec!["\n\naaaa".to_string()]);
+    }
+
+    #[test]
+    fn closes_and_reopens_a_code_block_split_across_parts() {
+        let message = "Games:\n```diff\n+ won\n- lost\n+ won\n```\nDone";
+        let parts = split_message(message, 24);
+
+        assert_eq!(parts, vec![
+            "Games:\n```diff\n+ won\n```".to_string(),
+            "```diff\n- lost\n+ won\n```".to_string(),
+            "Done".to_string()
+        ]);
+
+        for part in parts {
+            assert!(part.chars().count() <= 24);
+            assert_eq!(part.matches(CODE_FENCE).count() % 2, 0);
+        }
+    }
+}
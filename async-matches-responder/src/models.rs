@@ -1,12 +1,12 @@
 use lol;
 use common::discord_bot_types;
 use serde::{Deserialize, Serialize};
-use aws_sdk_dynamodb;
+use common::storage::BotStorage;
 
 pub struct Toolbox {
     pub lol_api_fetcher: lol::api_fetcher::BoundedHttpFetcher,
-    pub discord_http_client: reqwest::Client,
-    pub dynamo_client: aws_sdk_dynamodb::Client
+    pub discord_client: common::discord_rest::DiscordRestClient,
+    pub storage: Box<dyn BotStorage>
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -19,18 +19,106 @@ pub struct QueueRecord {
     pub body: String
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct DiscordResponseBody {
-    pub content: String
-}
-
 pub struct GamesOverTimeSummary {
     pub games: Vec<lol::models::UserGameSummary>,
     pub wins: u64,
     pub losses: u64,
+    pub played_for_millis: u64,
+
+    /**
+     * How each champion played in 'games' did, most played first
+     */
+    pub champions: Vec<ChampionSummary>,
+
+    /**
+     * How many games were played in the period. This is more than 'fetched_games' if we ran out of time fetching them.
+     */
+    pub total_games: usize,
+
+    /**
+     * How many games we fetched, including any that didn't match the filter and so aren't in 'games'
+     */
+    pub fetched_games: usize
+}
+
+/**
+ * What the user asked a /played summary to cover and show
+ */
+pub struct SummaryOptions {
+    pub period: SummaryPeriod,
+    pub filter: GameFilter,
+    pub detail: discord_bot_types::DetailLevel,
+
+    /**
+     * The accounts linked in the Discord server the command was used in
+     */
+    pub linked_accounts: Vec<common::linked_accounts::LinkedAccount>
+}
+
+/**
+ * The period of time a summary covers
+ */
+pub struct SummaryPeriod {
+    pub range: common::date_range::DateRange,
+
+    /**
+     * Whether the start of the range the user asked for was before Riot's match history begins, so had to be moved
+     */
+    pub truncated: bool
+}
+
+/**
+ * Narrows down which of the player's games are summarised
+ */
+pub struct GameFilter {
+    /**
+     * Only games in this queue (by queue ID). The match API filters by queue for us.
+     */
+    pub queue: Option<u64>,
+    pub champion: Option<lol::static_data::StaticEntry>,
+
+    /**
+     * Only games the player played in this position, e.g. 'JUNGLE'
+     */
+    pub role: Option<String>
+}
+
+impl GameFilter {
+    pub fn matches(&self, participant: &lol::models::Participant) -> bool {
+        let champion_matches = self.champion.as_ref().map(|champion| champion.id == participant.championId).unwrap_or(true);
+        let role_matches = self.role.as_ref().map(|role| role == &participant.teamPosition).unwrap_or(true);
+
+        return champion_matches && role_matches;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.queue.is_none() && self.champion.is_none() && self.role.is_none();
+    }
+}
+
+/**
+ * The totals over every game the player played on one champion
+ */
+pub struct ChampionSummary {
+    pub champion_name: String,
+    pub games: u64,
+    pub wins: u64,
+    pub kills: u64,
+    pub deaths: u64,
+    pub assists: u64,
+    pub creep_score: u64,
     pub played_for_millis: u64
 }
 
+/**
+ * A player's ranking in a queue, along with how it has changed over the period we were asked about (if we have
+ * a snapshot of their rank from around then)
+ */
+pub struct RankingWithChange {
+    pub entry: lol::models::LeagueEntry,
+    pub change: Option<String>
+}
+
 /**
  * Converts between an error received from the LoL API and
  * our internal representation of an error
@@ -50,4 +138,13 @@ pub fn to_bot_error(error: lol::models::LolApiError) -> discord_bot_types::BotEr
         statusCode: error_code,
         body: error.description
     }
-}
\ No newline at end of file
+}
+/**
+ * For when we can't tell what time it is, which we need to work out which games to look at
+ */
+pub fn to_clock_bot_error(error: std::time::SystemTimeError) -> discord_bot_types::BotError {
+    return discord_bot_types::BotError {
+        statusCode: 500,
+        body: format!("Could not get the current time: {}", error)
+    };
+}
@@ -0,0 +1,174 @@
+use common::discord_rest::{DiscordRestClient, MessagePayload};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::sync::watch::error::RecvError;
+use tokio::task::{JoinError, JoinHandle};
+use tokio::time::sleep;
+
+/**
+ * Discord rate limits edits to an interaction's messages, so we edit in progress updates at most this often
+ */
+const MIN_EDIT_INTERVAL: Duration = Duration::from_millis(1500);
+
+struct ProgressState {
+    player_name: String,
+    rank_line: Option<String>,
+    games_found: Option<usize>,
+    games_fetched: usize
+}
+
+/**
+ * Keeps the user's deferred message updated with how far along we are. Only the latest progress is sent,
+ * so updates that arrive faster than we're allowed to edit the message are skipped.
+ */
+pub struct ProgressReporter {
+    state: Mutex<ProgressState>,
+    sender: watch::Sender<Option<String>>
+}
+
+/**
+ * The task editing progress updates into the message. Dropping it stops the task, so that a progress edit can't
+ * land after (and overwrite) the message we send when a command times out or panics.
+ */
+pub struct ProgressTask {
+    handle: JoinHandle<()>
+}
+
+impl ProgressTask {
+    /**
+     * Waits for any edit in flight to be sent. The reporter must have been dropped first, or this never finishes.
+     */
+    pub async fn finish(mut self) -> Result<(), JoinError> {
+        return (&mut self.handle).await;
+    }
+}
+
+impl Drop for ProgressTask {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/**
+ * Starts editing progress updates into the original interaction message. Once the reporter has been dropped,
+ * finish the returned task before sending the final message.
+ */
+pub fn start_progress_reporter(
+    discord_client: DiscordRestClient,
+    application_id: String,
+    token: String,
+    player_name: &str) -> (ProgressReporter, ProgressTask) {
+
+    let (sender, mut receiver) = watch::channel(None);
+
+    let handle = tokio::spawn(async move {
+        let mut next = receiver.changed().await;
+
+        while next.is_ok() {
+            // Once the reporter is dropped the final message is about to replace the progress, so it isn't sent
+            if receiver.has_changed().is_err() {
+                break;
+            }
+
+            let message: Option<String> = receiver.borrow_and_update().clone();
+
+            if let Some(progress) = message {
+                let send_result = discord_client
+                    .edit_original_response(&application_id, &token, &MessagePayload::from_content(&progress))
+                    .await;
+
+                if let Err(err) = send_result {
+                    println!("Could not send progress update: {}", err);
+                }
+            }
+
+            next = wait_for_next_edit(&mut receiver).await;
+        }
+    });
+
+    let reporter = ProgressReporter {
+        state: Mutex::new(ProgressState {
+            player_name: player_name.to_string(),
+            rank_line: None,
+            games_found: None,
+            games_fetched: 0
+        }),
+        sender: sender
+    };
+
+    return (reporter, ProgressTask { handle: handle });
+}
+
+impl ProgressReporter {
+    pub fn rank_found(&self, rank_line: String) {
+        self.update(|state| state.rank_line = Some(rank_line));
+    }
+
+    /**
+     * Adds to the number of games we're fetching, for commands that fetch more than one player's games
+     */
+    pub fn games_found(&self, count: usize) {
+        self.update(|state| state.games_found = Some(state.games_found.unwrap_or(0) + count));
+    }
+
+    pub fn game_fetched(&self) {
+        self.update(|state| state.games_fetched = state.games_fetched + 1);
+    }
+
+    fn update<F: FnOnce(&mut ProgressState)>(&self, change: F) {
+        let message = match self.state.lock() {
+            Err(_) => return,
+            Ok(mut state) => {
+                change(&mut state);
+                render_progress(&state)
+            }
+        };
+
+        // Nothing is listening once the reporter task has stopped, which is fine
+        let _ = self.sender.send(Some(message));
+    }
+}
+
+/**
+ * Waits until we're allowed to edit the message again and there's new progress to send. Stops waiting as soon as
+ * the reporter is dropped.
+ */
+async fn wait_for_next_edit(receiver: &mut watch::Receiver<Option<String>>) -> Result<(), RecvError> {
+    let pause = sleep(MIN_EDIT_INTERVAL);
+    tokio::pin!(pause);
+
+    let mut changed_during_pause = false;
+
+    loop {
+        tokio::select! {
+            _ = &mut pause => break,
+            changed = receiver.changed() => {
+                changed?;
+                changed_during_pause = true;
+            }
+        }
+    }
+
+    if changed_during_pause {
+        return Ok(());
+    }
+
+    return receiver.changed().await;
+}
+
+fn render_progress(state: &ProgressState) -> String {
+    let mut message = "".to_string();
+
+    if let Some(rank_line) = &state.rank_line {
+        message.push_str(rank_line);
+        message.push_str("\n");
+    }
+
+    match state.games_found {
+        None => message.push_str(&format!("Looking up {}'s games…", state.player_name)),
+        Some(found) => message.push_str(&format!("Found {} games, fetched {}/{}…", found, state.games_fetched, found))
+    }
+
+    return message;
+}
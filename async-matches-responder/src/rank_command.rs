@@ -0,0 +1,127 @@
+use lol;
+use common::discord_bot_types;
+use crate::lol_command;
+use crate::models;
+use common::storage::BotStorage;
+
+pub async fn execute_rank_command(
+    lol_api_fetcher: &lol::api_fetcher::BoundedHttpFetcher,
+    storage: &dyn BotStorage,
+    command: &discord_bot_types::RankCommand) -> Result<String, discord_bot_types::BotError> {
+
+    let api_key = lol_command::get_api_key()?;
+    let mut rankings: Vec<lol::models::LeagueEntry> = fetch_and_record_rankings(lol_api_fetcher, storage, &api_key, &command.player_name, 0).await?
+        .into_iter()
+        .map(|ranking| ranking.entry)
+        .collect();
+
+    if rankings.is_empty() {
+        return Ok(format!("{} is not ranked in any queue.", command.player_name));
+    }
+
+    rankings.sort_by_key(|entry| lol::rank::queue_display_order(&entry.queueType));
+
+    let mut message = format!("**{}**\n", command.player_name);
+
+    for entry in rankings.iter() {
+        message.push_str(&create_queue_rank_string(entry));
+        message.push_str("\n");
+    }
+
+    return Ok(message);
+}
+
+/**
+ * Fetches the player's current rankings, works out how each has changed since the given time (in seconds since the
+ * epoch) and records them in the player's rank history
+ */
+pub async fn fetch_and_record_rankings(
+    lol_api_fetcher: &lol::api_fetcher::BoundedHttpFetcher,
+    storage: &dyn BotStorage,
+    api_key: &str,
+    player_name: &str,
+    since: u64) -> Result<Vec<models::RankingWithChange>, discord_bot_types::BotError> {
+
+    let summoner = lol::get_summoner(lol_api_fetcher, "euw1", player_name, api_key).await.map_err(models::to_bot_error)?;
+    let rankings = lol::get_league_rankings(lol_api_fetcher, "euw1", summoner.id.to_string(), api_key).await.map_err(models::to_bot_error)?;
+
+    let mut results: Vec<models::RankingWithChange> = Vec::new();
+
+    for entry in rankings.iter() {
+        // Failing to read the history shouldn't stop us showing the current rank
+        let history = common::rank_history::get_rank_snapshots(storage, &summoner.puuid, &entry.queueType).await.unwrap_or(Vec::new());
+        let change = common::rank_history::find_baseline_snapshot(&history, since)
+            .map(|baseline| lol::rank::format_rank_change(&baseline.entry, entry));
+
+        results.push(models::RankingWithChange {
+            entry: entry.clone(),
+            change: change
+        });
+    }
+
+    let track_result = common::rank_history::track_player(storage, &summoner.puuid, &summoner.id, player_name).await;
+    let store_result = common::rank_history::store_rank_snapshots(storage, &summoner.puuid, &rankings).await;
+    println!("Rank history update results: {:?}, {:?}", track_result, store_result);
+
+    return Ok(results);
+}
+
+/**
+ * The rank and how it changed, e.g. 'Gold II 45 LP (+62 LP, promoted from Gold III)'
+ */
+pub fn create_rank_with_change_string(ranking: &models::RankingWithChange) -> String {
+    let rank = format!("{} {} LP", lol::rank::format_rank(&ranking.entry), ranking.entry.leaguePoints);
+
+    return match &ranking.change {
+        None => rank,
+        Some(change) => format!("{} ({})", rank, change)
+    };
+}
+
+fn create_queue_rank_string(entry: &lol::models::LeagueEntry) -> String {
+    let mut result = format!(
+        "{}: {} {} LP, {}W {}L ({}% winrate)",
+        lol::rank::queue_display_name(&entry.queueType),
+        lol::rank::format_rank(entry),
+        entry.leaguePoints,
+        entry.wins,
+        entry.losses,
+        lol::rank::winrate_percentage(entry.wins, entry.losses)
+    );
+
+    let mut flags: Vec<&str> = Vec::new();
+
+    if entry.hotStreak {
+        flags.push("🔥 on a hot streak");
+    }
+
+    if entry.veteran {
+        flags.push("veteran");
+    }
+
+    if entry.freshBlood {
+        flags.push("fresh blood");
+    }
+
+    if entry.inactive {
+        flags.push("inactive");
+    }
+
+    if !flags.is_empty() {
+        result.push_str(&format!(" [{}]", flags.join(", ")));
+    }
+
+    if let Some(series) = &entry.miniSeries {
+        result.push_str(&format!("\n    In promos: {} ({} wins needed)", create_series_progress_string(&series.progress), series.target));
+    }
+
+    return result;
+}
+
+fn create_series_progress_string(progress: &str) -> String {
+    return progress.chars().map(|game| match game {
+        'W' => "✅",
+        'L' => "❌",
+        _ => "➖"
+    }).collect::<Vec<&str>>().join("");
+}
@@ -0,0 +1,101 @@
+use lol;
+use common::discord_bot_types::DetailLevel;
+
+/**
+ * How the player did in a game compared to the rest of their team
+ */
+pub struct GameStats {
+    pub creep_score_per_minute: f64,
+
+    /**
+     * The fractions (between 0 and 1) of their team's kills they took part in, and of their team's damage to
+     * champions and gold that was theirs
+     */
+    pub kill_participation: f64,
+    pub damage_share: f64,
+    pub gold_share: f64,
+
+    pub vision_score: u64,
+    pub double_kills: u64,
+    pub triple_kills: u64,
+    pub quadra_kills: u64,
+    pub penta_kills: u64
+}
+
+pub fn calculate_game_stats(game_summary: &lol::models::UserGameSummary) -> GameStats {
+    let participant = &game_summary.participant;
+    let team: Vec<&lol::models::Participant> = game_summary.participants.iter()
+        .filter(|teammate| teammate.teamId == participant.teamId)
+        .collect();
+
+    let team_kills: u64 = team.iter().map(|teammate| teammate.kills).sum();
+    let team_damage: u64 = team.iter().map(|teammate| teammate.totalDamageDealtToChampions).sum();
+    let team_gold: u64 = team.iter().map(|teammate| teammate.goldEarned).sum();
+
+    return GameStats {
+        creep_score_per_minute: lol::stats::creep_score_per_minute(participant.totalMinionsKilled + participant.neutralMinionsKilled, game_summary.game_duration_millis),
+        kill_participation: fraction(participant.kills + participant.assists, team_kills as f64),
+        damage_share: fraction(participant.totalDamageDealtToChampions, team_damage as f64),
+        gold_share: fraction(participant.goldEarned, team_gold as f64),
+        vision_score: participant.visionScore,
+        double_kills: participant.doubleKills,
+        triple_kills: participant.tripleKills,
+        quadra_kills: participant.quadraKills,
+        penta_kills: participant.pentaKills
+    };
+}
+
+/**
+ * The extra stats to show after a game's K/D/A at the given detail level, e.g.
+ * ' | 7.2 CS/min, 64% KP, 28% dmg | 22% gold, 31 vision, 1 triple kill'. Empty for the basic level.
+ */
+pub fn create_detail_string(game_summary: &lol::models::UserGameSummary, detail: DetailLevel) -> String {
+    if detail == DetailLevel::Basic {
+        return "".to_string();
+    }
+
+    let stats = calculate_game_stats(game_summary);
+    let mut result = format!(
+        " | {:.1} CS/min, {}% KP, {}% dmg",
+        stats.creep_score_per_minute,
+        percentage(stats.kill_participation),
+        percentage(stats.damage_share)
+    );
+
+    if detail == DetailLevel::Full {
+        result.push_str(&format!(" | {}% gold, {} vision", percentage(stats.gold_share), stats.vision_score));
+
+        let multikills = create_multikills_string(&stats);
+        if !multikills.is_empty() {
+            result.push_str(&format!(", {}", multikills));
+        }
+    }
+
+    return result;
+}
+
+/**
+ * e.g. '2 double kills, 1 penta kill'
+ */
+fn create_multikills_string(stats: &GameStats) -> String {
+    let multikills = [
+        (stats.double_kills, "double kill"),
+        (stats.triple_kills, "triple kill"),
+        (stats.quadra_kills, "quadra kill"),
+        (stats.penta_kills, "penta kill")
+    ];
+
+    return multikills.iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, name)| format!("{} {}{}", count, name, if *count == 1 {""} else {"s"}))
+        .collect::<Vec<String>>()
+        .join(", ");
+}
+
+fn fraction(value: u64, total: f64) -> f64 {
+    return if total > 0.0 {value as f64 / total} else {0.0};
+}
+
+fn percentage(fraction: f64) -> u64 {
+    return (fraction * 100.0).round() as u64;
+}
@@ -0,0 +1,58 @@
+use lol;
+use common::discord_bot_types;
+use common::match_tracking::MatchTrackedPlayer;
+use common::storage::BotStorage;
+use crate::lol_command;
+use crate::models;
+
+/**
+ * How far back we look for the player's latest game when we start tracking them
+ */
+const LATEST_GAME_LOOKBACK_DAYS: u64 = 2;
+
+pub async fn execute_track_command(
+    lol_api_fetcher: &lol::api_fetcher::BoundedHttpFetcher,
+    storage: &dyn BotStorage,
+    command: &discord_bot_types::TrackCommand) -> Result<String, discord_bot_types::BotError> {
+
+    let api_key = lol_command::get_api_key()?;
+    let summoner = lol::get_summoner(lol_api_fetcher, "euw1", &command.player_name, &api_key).await.map_err(models::to_bot_error)?;
+
+    if command.stop {
+        common::match_tracking::untrack_matches(storage, &summoner.puuid, &command.channel_id).await.map_err(to_storage_bot_error)?;
+        return Ok(format!("I'll stop posting {}'s games in <#{}>.", command.player_name, command.channel_id));
+    }
+
+    // Only games finished from now on are posted, so remember the latest one they've already played. If they
+    // haven't played recently, an empty match ID means any game we see next is new.
+    if common::match_tracking::get_last_seen_match(storage, &summoner.puuid).await.map_err(to_storage_bot_error)?.is_none() {
+        let now = common::date_range::now_seconds().map_err(models::to_clock_bot_error)?;
+        let game_ids = lol::get_game_ids(lol_api_fetcher, &api_key, "europe", &summoner.puuid, &common::date_range::last_days(LATEST_GAME_LOOKBACK_DAYS, now), &lol::models::GameIdsFilter::default()).await
+            .map_err(models::to_bot_error)?;
+        let latest_game_id = game_ids.first().map(|game_id| game_id.to_string()).unwrap_or("".to_string());
+
+        common::match_tracking::store_last_seen_match(storage, &summoner.puuid, &latest_game_id).await.map_err(to_storage_bot_error)?;
+    }
+
+    common::match_tracking::track_matches(storage, &MatchTrackedPlayer {
+        puuid: summoner.puuid.to_string(),
+        player_name: command.player_name.to_string(),
+        guild_id: command.guild_id.to_string(),
+        channel_id: command.channel_id.to_string()
+    }).await.map_err(to_storage_bot_error)?;
+
+    // A snapshot of their rank now lets us say how much LP their next game won or lost
+    let rankings = lol::get_league_rankings(lol_api_fetcher, "euw1", summoner.id.to_string(), &api_key).await.map_err(models::to_bot_error)?;
+    let track_result = common::rank_history::track_player(storage, &summoner.puuid, &summoner.id, &command.player_name).await;
+    let store_result = common::rank_history::store_rank_snapshots(storage, &summoner.puuid, &rankings).await;
+    println!("Rank history update results: {:?}, {:?}", track_result, store_result);
+
+    return Ok(format!("I'll post in <#{}> whenever {} finishes a game. Use /track with stop to undo this.", command.channel_id, command.player_name));
+}
+
+fn to_storage_bot_error(err: common::storage::StorageError) -> discord_bot_types::BotError {
+    return discord_bot_types::BotError {
+        statusCode: 500,
+        body: format!("Could not update tracked players: {}", err)
+    };
+}
@@ -0,0 +1,276 @@
+use chrono::{Date, Datelike, Duration, NaiveDate, TimeZone, Utc};
+use std::time::{SystemTime, SystemTimeError, UNIX_EPOCH};
+
+/**
+ * When each League season started. Riot don't publish these through the API, so a new season needs adding here.
+ */
+const SEASON_STARTS: [(i32, u32, u32); 5] = [
+    (2022, 1, 7),
+    (2023, 1, 10),
+    (2024, 1, 10),
+    (2025, 1, 9),
+    (2026, 1, 8)
+];
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+pub use lol::models::DateRange;
+
+/**
+ * Works out the period the user asked for from the command's options. A named range (e.g. 'this_month') takes
+ * precedence, then 'since' and 'until' dates (in YYYY-MM-DD form), then a number of days up to now. The error is a
+ * message explaining to the user what was wrong with their options.
+ */
+pub fn resolve_date_range(
+    range: Option<&str>,
+    since: Option<&str>,
+    until: Option<&str>,
+    days: Option<u64>,
+    now: u64) -> Result<DateRange, String> {
+
+    if range.is_some() && (since.is_some() || until.is_some()) {
+        return Err("Choose either a range or since/until dates, not both.".to_string());
+    }
+
+    let result = match (range, since, until) {
+        (Some(range), _, _) => resolve_named_range(range, now)?,
+        (None, None, None) => last_days(days.unwrap_or(7), now),
+        (None, since, until) => {
+            let start = since.map(parse_date).transpose()?;
+            let end = until.map(parse_date).transpose()?;
+
+            // Until is inclusive for the user, so the range ends as the next day starts
+            let end_timestamp = end.map(|date| (date + Duration::days(1)).and_hms(0, 0, 0).timestamp() as u64).unwrap_or(now).min(now);
+            let start_timestamp = start
+                .map(|date| date.and_hms(0, 0, 0).timestamp() as u64)
+                .unwrap_or(end_timestamp.saturating_sub(7 * 86400));
+
+            let description = match (start, end) {
+                (Some(start), Some(end)) => format!("between {} and {}", format_date(start), format_date(end)),
+                (Some(start), None) => format!("since {}", format_date(start)),
+                (None, Some(end)) => format!("in the 7 days up to {}", format_date(end)),
+                (None, None) => "".to_string()
+            };
+
+            DateRange {
+                start: start_timestamp,
+                end: end_timestamp,
+                description: description
+            }
+        }
+    };
+
+    if result.start >= result.end {
+        return Err("The start of the range must be before its end, and not in the future.".to_string());
+    }
+
+    if result.end <= lol::MATCH_HISTORY_START {
+        return Err(format!("Riot only keep match history from {}, so I can't look any earlier than that.", format_timestamp(lol::MATCH_HISTORY_START)));
+    }
+
+    return Ok(result);
+}
+
+/**
+ * Moves the start of the range up to when Riot's match history begins if it is earlier than that. Returns whether
+ * the range had to be cut short.
+ */
+pub fn clamp_to_match_history(range: &DateRange) -> (DateRange, bool) {
+    if range.start >= lol::MATCH_HISTORY_START {
+        return (range.clone(), false);
+    }
+
+    let clamped = DateRange {
+        start: lol::MATCH_HISTORY_START,
+        end: range.end,
+        description: range.description.to_string()
+    };
+
+    return (clamped, true);
+}
+
+/**
+ * The given number of days up to now
+ */
+pub fn last_days(days: u64, now: u64) -> DateRange {
+    return DateRange {
+        start: now.saturating_sub(days * 86400),
+        end: now,
+        description: format!("over the last {} days", days)
+    };
+}
+
+/**
+ * The current time in seconds since the epoch
+ */
+pub fn now_seconds() -> Result<u64, SystemTimeError> {
+    return SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.as_secs());
+}
+
+/**
+ * The current time in milliseconds since the epoch, for comparing with the timestamps Riot give games
+ */
+pub fn now_millis() -> Result<u64, SystemTimeError> {
+    return SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.as_millis() as u64);
+}
+
+/**
+ * e.g. '16 Jun 2021'
+ */
+pub fn format_timestamp(timestamp: u64) -> String {
+    return format_date(Utc.timestamp(timestamp as i64, 0).date());
+}
+
+/**
+ * e.g. '3 hours and 20 minutes'
+ */
+pub fn format_duration(millis: u64) -> String {
+    let minutes = millis / 60000;
+    return format!("{} hours and {} minutes", minutes / 60, minutes % 60);
+}
+
+fn resolve_named_range(range: &str, now: u64) -> Result<DateRange, String> {
+    let today = Utc.timestamp(now as i64, 0).date();
+    let start_of_month = Utc.ymd(today.year(), today.month(), 1);
+
+    let (start, end, description) = match range {
+        "this_week" => {
+            let start_of_week = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+            (start_of_week, None, "this week".to_string())
+        },
+        "this_month" => (start_of_month, None, "this month".to_string()),
+        "last_month" => {
+            let last_month = start_of_month - Duration::days(1);
+            (Utc.ymd(last_month.year(), last_month.month(), 1), Some(start_of_month), "last month".to_string())
+        },
+        "this_season" => {
+            let (season, start) = find_season(today, 0).ok_or("I don't know when this season started.".to_string())?;
+            (start, None, format!("this season ({})", season))
+        },
+        "last_season" => {
+            let (season, start) = find_season(today, 1).ok_or("I don't know when last season started.".to_string())?;
+            let (_, end) = find_season(today, 0).ok_or("I don't know when this season started.".to_string())?;
+            (start, Some(end), format!("last season ({})", season))
+        },
+        other => return Err(format!("Unrecognised range {}", other))
+    };
+
+    return Ok(DateRange {
+        start: start.and_hms(0, 0, 0).timestamp() as u64,
+        end: end.map(|end| end.and_hms(0, 0, 0).timestamp() as u64).unwrap_or(now),
+        description: description
+    });
+}
+
+/**
+ * The season the given number of seasons before the one that the given day is in, with the day it started
+ */
+fn find_season(day: Date<Utc>, seasons_ago: usize) -> Option<(i32, Date<Utc>)> {
+    let started = SEASON_STARTS.iter()
+        .map(|(year, month, day_of_month)| (*year, Utc.ymd(*year, *month, *day_of_month)))
+        .filter(|(_, start)| *start <= day)
+        .collect::<Vec<(i32, Date<Utc>)>>();
+
+    return started.len().checked_sub(seasons_ago + 1).map(|index| started[index]);
+}
+
+fn parse_date(date: &str) -> Result<Date<Utc>, String> {
+    return NaiveDate::parse_from_str(date.trim(), DATE_FORMAT)
+        .map(|date| Utc.from_utc_date(&date))
+        .map_err(|_| format!("I couldn't understand the date '{}'. Dates should look like 2024-01-31.", date));
+}
+
+fn format_date(date: Date<Utc>) -> String {
+    return date.format("%-d %b %Y").to_string();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Wednesday 15 May 2024 12:00 UTC
+    const NOW: u64 = 1715774400;
+
+    fn timestamp(year: i32, month: u32, day: u32) -> u64 {
+        return Utc.ymd(year, month, day).and_hms(0, 0, 0).timestamp() as u64;
+    }
+
+    #[test]
+    fn defaults_to_the_last_7_days() {
+        let range = resolve_date_range(None, None, None, None, NOW).unwrap();
+
+        assert_eq!(range.start, NOW - 7 * 86400);
+        assert_eq!(range.end, NOW);
+        assert_eq!(range.description, "over the last 7 days");
+    }
+
+    #[test]
+    fn uses_the_number_of_days_given() {
+        let range = resolve_date_range(None, None, None, Some(30), NOW).unwrap();
+
+        assert_eq!(range.start, NOW - 30 * 86400);
+        assert_eq!(range.description, "over the last 30 days");
+    }
+
+    #[test]
+    fn resolves_named_ranges() {
+        let this_week = resolve_date_range(Some("this_week"), None, None, None, NOW).unwrap();
+        assert_eq!(this_week.start, timestamp(2024, 5, 13));
+        assert_eq!(this_week.end, NOW);
+
+        let last_month = resolve_date_range(Some("last_month"), None, None, None, NOW).unwrap();
+        assert_eq!(last_month.start, timestamp(2024, 4, 1));
+        assert_eq!(last_month.end, timestamp(2024, 5, 1));
+
+        let last_season = resolve_date_range(Some("last_season"), None, None, None, NOW).unwrap();
+        assert_eq!(last_season.start, timestamp(2023, 1, 10));
+        assert_eq!(last_season.end, timestamp(2024, 1, 10));
+        assert_eq!(last_season.description, "last season (2023)");
+    }
+
+    #[test]
+    fn until_date_is_inclusive() {
+        let range = resolve_date_range(None, Some("2024-05-01"), Some("2024-05-03"), None, NOW).unwrap();
+
+        assert_eq!(range.start, timestamp(2024, 5, 1));
+        assert_eq!(range.end, timestamp(2024, 5, 4));
+        assert_eq!(range.description, "between 1 May 2024 and 3 May 2024");
+    }
+
+    #[test]
+    fn since_date_runs_up_to_now() {
+        let range = resolve_date_range(None, Some("2024-05-10"), None, None, NOW).unwrap();
+
+        assert_eq!(range.start, timestamp(2024, 5, 10));
+        assert_eq!(range.end, NOW);
+    }
+
+    #[test]
+    fn rejects_bad_options() {
+        assert!(resolve_date_range(Some("this_month"), Some("2024-05-01"), None, None, NOW).is_err());
+        assert!(resolve_date_range(Some("next_month"), None, None, None, NOW).is_err());
+        assert!(resolve_date_range(None, Some("01/05/2024"), None, None, NOW).is_err());
+        assert!(resolve_date_range(None, Some("2024-05-10"), Some("2024-05-01"), None, NOW).is_err());
+        assert!(resolve_date_range(None, Some("2024-06-01"), None, None, NOW).is_err());
+        assert!(resolve_date_range(None, None, Some("2021-01-01"), None, NOW).is_err());
+    }
+
+    #[test]
+    fn clamps_ranges_starting_before_match_history() {
+        let range = resolve_date_range(None, Some("2021-01-01"), None, None, NOW).unwrap();
+        let (clamped, truncated) = clamp_to_match_history(&range);
+
+        assert!(truncated);
+        assert_eq!(clamped.start, lol::MATCH_HISTORY_START);
+        assert_eq!(clamped.end, range.end);
+    }
+
+    #[test]
+    fn leaves_ranges_within_match_history_alone() {
+        let range = resolve_date_range(None, None, None, Some(7), NOW).unwrap();
+        let (clamped, truncated) = clamp_to_match_history(&range);
+
+        assert!(!truncated);
+        assert_eq!(clamped.start, range.start);
+    }
+}
@@ -1,5 +1,15 @@
 use serde::{Deserialize, Serialize};
 
+/**
+ * Message flag that hides embeds for any links in the message content
+ */
+pub const SUPPRESS_EMBEDS_FLAG: u64 = 1 << 2;
+
+/**
+ * Message flag that makes a response visible only to the user who invoked the command
+ */
+pub const EPHEMERAL_FLAG: u64 = 1 << 6;
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct BotError {
     pub statusCode: u64,
@@ -12,12 +22,101 @@ pub struct Headers {
     pub contentType: String
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Default)]
 pub struct Data {
     // This is maybe a bit hacky. Could use a generic for 'data' field instead?
     pub tts: Option<bool>,
     pub content: Option<String>,
-    pub choices: Option<Vec<StringChoice>>
+    pub choices: Option<Vec<StringChoice>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flags: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_mentions: Option<AllowedMentions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embeds: Option<Vec<Embed>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub components: Option<Vec<Component>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attachments: Option<Vec<Attachment>>
+}
+
+/**
+ * Which mentions in the message content should actually notify people. An empty 'parse' list means nobody is pinged.
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AllowedMentions {
+    pub parse: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub users: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub roles: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replied_user: Option<bool>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Embed {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Vec<EmbedField>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub footer: Option<EmbedFooter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<EmbedImage>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EmbedField {
+    pub name: String,
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inline: Option<bool>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EmbedFooter {
+    pub text: String
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EmbedImage {
+    pub url: String
+}
+
+/**
+ * A message component. Action rows (type 1) hold other components such as buttons (type 2) in 'components'.
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Component {
+    #[serde(rename(serialize = "type", deserialize = "type"))]
+    pub typeField: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub components: Option<Vec<Component>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub style: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disabled: Option<bool>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Attachment {
+    pub id: String,
+    pub filename: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>
 }
 
 #[derive(Serialize, Deserialize)]
@@ -52,10 +151,17 @@ pub struct LambdaBotResponse {
 pub struct DiscordReceivedCommand {
     #[serde(rename(serialize = "type", deserialize = "type"))]
     pub typeField: u64,
+    pub id: String,
     pub token: String,
     pub application_id: String,
     pub data: Option<Command>,
-    pub member: Option<Member>
+    pub member: Option<Member>,
+
+    /**
+     * The Discord server the command was used in. Missing for commands sent in DMs.
+     */
+    pub guild_id: Option<String>,
+    pub channel_id: Option<String>
 }
 
 #[derive(Serialize, Deserialize)]
@@ -80,17 +186,34 @@ pub struct NumberCommandOption {
     pub focused: Option<bool>
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct BooleanCommandOption {
+    #[serde(rename(serialize = "type", deserialize = "type"))]
+    typeField: u64,
+    pub name: String,
+    pub value: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub focused: Option<bool>
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum CommandOption {
     NumberCommandOption(NumberCommandOption),
-    StringCommandOption(StringCommandOption)
+    StringCommandOption(StringCommandOption),
+    BooleanCommandOption(BooleanCommandOption)
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct Command {
     pub id: String,
     pub name: String,
+
+    /**
+     * Discord leaves this out for commands without options
+     */
+    #[serde(default)]
     pub options: Vec<CommandOption>
 }
 
@@ -105,6 +228,34 @@ pub struct User {
     pub username: String
 }
 
+/**
+ * How much to show about each game in a summary
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DetailLevel {
+    /**
+     * Champion, K/D/A and the result
+     */
+    Basic,
+
+    /**
+     * Also CS per minute, kill participation and damage share
+     */
+    Stats,
+
+    /**
+     * Also gold share, vision score and multikills
+     */
+    Full
+}
+
+impl Default for DetailLevel {
+    fn default() -> Self {
+        return DetailLevel::Basic;
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct PlayedCommand {
     pub token: String,
@@ -112,5 +263,306 @@ pub struct PlayedCommand {
     pub discord_user_id: String,
     pub player_name: String,
     pub days: u64,
-    pub game_type: Option<String>
-}
\ No newline at end of file
+
+    /**
+     * The period to summarise. Commands queued before ranges were supported only have 'days'.
+     */
+    #[serde(default)]
+    pub range: Option<crate::date_range::DateRange>,
+    pub game_type: Option<String>,
+
+    /**
+     * The Discord server the command was used in, whose linked accounts are highlighted among the player's duo partners
+     */
+    #[serde(default)]
+    pub guild_id: Option<String>,
+
+    /**
+     * How much detail to show for each game
+     */
+    #[serde(default)]
+    pub detail: DetailLevel,
+
+    /**
+     * Only summarise games in this queue (by queue ID)
+     */
+    #[serde(default)]
+    pub queue: Option<u64>,
+
+    /**
+     * Only summarise games played on this champion (by name)
+     */
+    #[serde(default)]
+    pub champion: Option<String>,
+
+    /**
+     * Only summarise games played in this position, e.g. 'JUNGLE'
+     */
+    #[serde(default)]
+    pub role: Option<String>,
+
+    /**
+     * Whether the summary should only be visible to the user who asked for it
+     */
+    #[serde(default)]
+    pub private: bool
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RankCommand {
+    pub token: String,
+    pub application_id: String,
+    pub discord_user_id: String,
+    pub player_name: String,
+
+    #[serde(default)]
+    pub private: bool
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LiveCommand {
+    pub token: String,
+    pub application_id: String,
+    pub discord_user_id: String,
+    pub player_name: String,
+
+    #[serde(default)]
+    pub private: bool
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MasteryCommand {
+    pub token: String,
+    pub application_id: String,
+    pub discord_user_id: String,
+    pub player_name: String,
+
+    /**
+     * How many of the player's top champions to list
+     */
+    pub count: Option<u64>,
+
+    /**
+     * When given, only the mastery of this champion (by name) is shown
+     */
+    pub champion: Option<String>,
+
+    #[serde(default)]
+    pub private: bool
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CompareCommand {
+    pub token: String,
+    pub application_id: String,
+    pub discord_user_id: String,
+    pub player_name: String,
+    pub other_player_name: String,
+    pub range: crate::date_range::DateRange,
+
+    #[serde(default)]
+    pub private: bool
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LinkCommand {
+    pub token: String,
+    pub application_id: String,
+    pub discord_user_id: String,
+    pub player_name: String,
+    pub guild_id: String,
+
+    #[serde(default)]
+    pub private: bool
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TrackCommand {
+    pub token: String,
+    pub application_id: String,
+    pub discord_user_id: String,
+    pub player_name: String,
+    pub guild_id: String,
+
+    /**
+     * The channel to post the player's finished games in
+     */
+    pub channel_id: String,
+
+    /**
+     * Whether to stop tracking the player in the channel rather than start
+     */
+    #[serde(default)]
+    pub stop: bool,
+
+    #[serde(default)]
+    pub private: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetAction {
+    Set,
+    View,
+    Clear
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BudgetCommand {
+    pub token: String,
+    pub application_id: String,
+    pub discord_user_id: String,
+    pub action: BudgetAction,
+
+    /**
+     * The League account to budget. If empty when setting a budget, the account the user linked in the server is used.
+     */
+    #[serde(default)]
+    pub player_name: String,
+    pub guild_id: Option<String>,
+
+    /**
+     * The channel to warn the user in when they go over their budget, unless they asked for a DM
+     */
+    pub channel_id: Option<String>,
+    pub hours: Option<u64>,
+    pub period: crate::playtime_budgets::BudgetPeriod,
+    #[serde(default)]
+    pub dm: bool,
+
+    #[serde(default)]
+    pub private: bool
+}
+
+/**
+ * A command sent via the queue for the async responder to carry out
+ */
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum QueuedCommand {
+    Played(PlayedCommand),
+    Rank(RankCommand),
+    Live(LiveCommand),
+    Mastery(MasteryCommand),
+    Compare(CompareCommand),
+    Link(LinkCommand),
+    Track(TrackCommand),
+    Budget(BudgetCommand)
+}
+
+impl QueuedCommand {
+    /**
+     * Reads a command from the queue. Before there were other commands, a bare PlayedCommand was queued without a
+     * 'command' tag, so anything still queued from then is read as one.
+     */
+    pub fn from_json(body: &str) -> Result<QueuedCommand, serde_json::Error> {
+        return serde_json::from_str::<QueuedCommand>(body).or_else(|err| {
+            serde_json::from_str::<PlayedCommand>(body).map(QueuedCommand::Played).map_err(|_| err)
+        });
+    }
+
+    pub fn application_id(&self) -> &str {
+        match self {
+            QueuedCommand::Played(command) => &command.application_id,
+            QueuedCommand::Rank(command) => &command.application_id,
+            QueuedCommand::Live(command) => &command.application_id,
+            QueuedCommand::Mastery(command) => &command.application_id,
+            QueuedCommand::Compare(command) => &command.application_id,
+            QueuedCommand::Link(command) => &command.application_id,
+            QueuedCommand::Track(command) => &command.application_id,
+            QueuedCommand::Budget(command) => &command.application_id
+        }
+    }
+
+    pub fn token(&self) -> &str {
+        match self {
+            QueuedCommand::Played(command) => &command.token,
+            QueuedCommand::Rank(command) => &command.token,
+            QueuedCommand::Live(command) => &command.token,
+            QueuedCommand::Mastery(command) => &command.token,
+            QueuedCommand::Compare(command) => &command.token,
+            QueuedCommand::Link(command) => &command.token,
+            QueuedCommand::Track(command) => &command.token,
+            QueuedCommand::Budget(command) => &command.token
+        }
+    }
+
+    pub fn discord_user_id(&self) -> &str {
+        match self {
+            QueuedCommand::Played(command) => &command.discord_user_id,
+            QueuedCommand::Rank(command) => &command.discord_user_id,
+            QueuedCommand::Live(command) => &command.discord_user_id,
+            QueuedCommand::Mastery(command) => &command.discord_user_id,
+            QueuedCommand::Compare(command) => &command.discord_user_id,
+            QueuedCommand::Link(command) => &command.discord_user_id,
+            QueuedCommand::Track(command) => &command.discord_user_id,
+            QueuedCommand::Budget(command) => &command.discord_user_id
+        }
+    }
+
+    /**
+     * The player the command is about, which we remember for the user's autocomplete suggestions
+     */
+    pub fn player_name(&self) -> &str {
+        match self {
+            QueuedCommand::Played(command) => &command.player_name,
+            QueuedCommand::Rank(command) => &command.player_name,
+            QueuedCommand::Live(command) => &command.player_name,
+            QueuedCommand::Mastery(command) => &command.player_name,
+            QueuedCommand::Compare(command) => &command.player_name,
+            QueuedCommand::Link(command) => &command.player_name,
+            QueuedCommand::Track(command) => &command.player_name,
+            QueuedCommand::Budget(command) => &command.player_name
+        }
+    }
+
+    /**
+     * Whether the response should only be visible to the user who invoked the command
+     */
+    pub fn is_private(&self) -> bool {
+        match self {
+            QueuedCommand::Played(command) => command.private,
+            QueuedCommand::Rank(command) => command.private,
+            QueuedCommand::Live(command) => command.private,
+            QueuedCommand::Mastery(command) => command.private,
+            QueuedCommand::Compare(command) => command.private,
+            QueuedCommand::Link(command) => command.private,
+            QueuedCommand::Track(command) => command.private,
+            QueuedCommand::Budget(command) => command.private
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_tagged_commands() {
+        let body = r#"{"command": "rank", "token": "token", "application_id": "app", "discord_user_id": "user", "player_name": "Faker"}"#;
+
+        match QueuedCommand::from_json(body).unwrap() {
+            QueuedCommand::Rank(command) => assert_eq!(command.player_name, "Faker"),
+            _ => panic!("Expected a rank command")
+        }
+    }
+
+    #[test]
+    fn reads_untagged_played_commands_queued_before_other_commands() {
+        let body = r#"{"token": "token", "application_id": "app", "discord_user_id": "user", "player_name": "Faker", "days": 3, "game_type": null}"#;
+
+        match QueuedCommand::from_json(body).unwrap() {
+            QueuedCommand::Played(command) => {
+                assert_eq!(command.days, 3);
+                assert!(command.range.is_none());
+            },
+            _ => panic!("Expected a played command")
+        }
+    }
+
+    #[test]
+    fn rejects_unreadable_commands() {
+        assert!(QueuedCommand::from_json(r#"{"command": "rank", "token": "token"}"#).is_err());
+        assert!(QueuedCommand::from_json("not json").is_err());
+    }
+}
@@ -0,0 +1,391 @@
+use crate::discord_bot_types;
+use reqwest::{Method, Response};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::{sleep, sleep_until, Instant};
+
+const DISCORD_API_URL: &str = "https://discord.com/api/v10";
+
+/**
+ * How many times a request is sent before giving up when Discord keeps rate limiting us or erroring
+ */
+const MAX_ATTEMPTS: u32 = 4;
+const SERVER_ERROR_BASE_DELAY_MILLIS: u64 = 250;
+
+#[derive(Debug)]
+pub enum DiscordRestError {
+    /**
+     * Discord still rate limited the request after we'd retried it as many times as we're willing to
+     */
+    RateLimited { retry_after: Duration },
+    /**
+     * Discord responded with an unsuccessful status code
+     */
+    Http { status: u16, body: String },
+    /**
+     * The request never got a response (e.g. connection problems)
+     */
+    Request(String),
+    /**
+     * We couldn't write the request or read the response body
+     */
+    Serialization(String),
+    /**
+     * The route needs the bot to authenticate, but no bot token is configured
+     */
+    MissingBotToken
+}
+
+impl std::fmt::Display for DiscordRestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiscordRestError::RateLimited { retry_after } => write!(f, "Rate limited by Discord, retry after {:?}", retry_after),
+            DiscordRestError::Http { status, body } => write!(f, "Discord responded with HTTP {}: {}", status, body),
+            DiscordRestError::Request(err) => write!(f, "Could not send request to Discord: {}", err),
+            DiscordRestError::Serialization(err) => write!(f, "Could not (de)serialize Discord payload: {}", err),
+            DiscordRestError::MissingBotToken => write!(f, "Missing DISCORD_BOT_TOKEN environment variable")
+        }
+    }
+}
+
+impl std::error::Error for DiscordRestError {}
+
+/**
+ * The body of a message we send (or edit) via the REST API
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct MessagePayload {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embeds: Option<Vec<discord_bot_types::Embed>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_mentions: Option<discord_bot_types::AllowedMentions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub components: Option<Vec<discord_bot_types::Component>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flags: Option<u64>
+}
+
+impl MessagePayload {
+    /**
+     * A plain text message that doesn't ping anyone mentioned in it
+     */
+    pub fn from_content(content: &str) -> MessagePayload {
+        return MessagePayload {
+            content: Some(content.to_string()),
+            allowed_mentions: Some(discord_bot_types::AllowedMentions::default()),
+            ..Default::default()
+        };
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DiscordMessage {
+    pub id: String,
+    pub channel_id: String
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DiscordChannel {
+    pub id: String
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct CreateDmPayload {
+    recipient_id: String
+}
+
+struct BucketState {
+    remaining: u64,
+    reset_at: Instant
+}
+
+#[derive(Default)]
+struct RateLimits {
+    /**
+     * Which rate limit bucket Discord told us each route belongs to
+     */
+    route_buckets: HashMap<String, String>,
+    /**
+     * The state of each bucket, keyed by the bucket and the route's major parameter (rate limits are per channel,
+     * webhook, etc. even within the same bucket)
+     */
+    buckets: HashMap<String, BucketState>
+}
+
+/**
+ * Identifies a route for rate limiting purposes. The route is the method and path with all parameters (such as
+ * message IDs and interaction tokens) replaced by placeholders, so it is safe to log. The major parameter is the
+ * channel / webhook the route is for, which for webhooks includes the interaction token, so it must never be logged.
+ */
+struct Route {
+    route: String,
+    major_parameter: String
+}
+
+/**
+ * A client for the Discord REST API that waits out rate limit buckets before sending requests,
+ * and retries requests that are rate limited or fail with a server error. Cloning it is cheap and
+ * the clones share their rate limit state.
+ */
+#[derive(Clone)]
+pub struct DiscordRestClient {
+    http_client: reqwest::Client,
+    bot_token: Option<String>,
+    rate_limits: Arc<Mutex<RateLimits>>
+}
+
+pub fn create_discord_client(bot_token: Option<String>) -> DiscordRestClient {
+    return DiscordRestClient {
+        http_client: reqwest::Client::new(),
+        bot_token: bot_token,
+        rate_limits: Arc::new(Mutex::new(RateLimits::default()))
+    };
+}
+
+/**
+ * Creates a client using the bot token in the DISCORD_BOT_TOKEN environment variable, if there is one. Routes
+ * that act on an interaction's webhook don't need the bot token.
+ */
+pub fn create_discord_client_from_env() -> DiscordRestClient {
+    return create_discord_client(env::var("DISCORD_BOT_TOKEN").ok());
+}
+
+impl DiscordRestClient {
+    /**
+     * Edits the original response to an interaction (e.g. replacing the deferred 'thinking...' message)
+     */
+    pub async fn edit_original_response(&self, application_id: &str, token: &str, message: &MessagePayload) -> Result<DiscordMessage, DiscordRestError> {
+        let route = Route {
+            route: "PATCH /webhooks/:application_id/:token/messages/@original".to_string(),
+            major_parameter: format!("{}/{}", application_id, token)
+        };
+
+        let url = format!("{}/webhooks/{}/{}/messages/@original", DISCORD_API_URL, application_id, token);
+        let response = self.send(Method::PATCH, &url, route, Some(message), false).await?;
+
+        return read_json(response).await;
+    }
+
+    /**
+     * Sends another message in response to an interaction, after the original response
+     */
+    pub async fn create_followup_message(&self, application_id: &str, token: &str, message: &MessagePayload) -> Result<DiscordMessage, DiscordRestError> {
+        let route = Route {
+            route: "POST /webhooks/:application_id/:token".to_string(),
+            major_parameter: format!("{}/{}", application_id, token)
+        };
+
+        // Without 'wait' Discord doesn't tell us about the message it created
+        let url = format!("{}/webhooks/{}/{}?wait=true", DISCORD_API_URL, application_id, token);
+        let response = self.send(Method::POST, &url, route, Some(message), false).await?;
+
+        return read_json(response).await;
+    }
+
+    /**
+     * Deletes a message sent in response to an interaction. Use '@original' as the message ID to delete the original response.
+     */
+    pub async fn delete_followup_message(&self, application_id: &str, token: &str, message_id: &str) -> Result<(), DiscordRestError> {
+        let route = Route {
+            route: "DELETE /webhooks/:application_id/:token/messages/:message_id".to_string(),
+            major_parameter: format!("{}/{}", application_id, token)
+        };
+
+        let url = format!("{}/webhooks/{}/{}/messages/{}", DISCORD_API_URL, application_id, token, message_id);
+        self.send::<MessagePayload>(Method::DELETE, &url, route, None, false).await?;
+
+        return Ok(());
+    }
+
+    /**
+     * Posts a message to a channel as the bot. Needs a bot token.
+     */
+    pub async fn create_channel_message(&self, channel_id: &str, message: &MessagePayload) -> Result<DiscordMessage, DiscordRestError> {
+        let route = Route {
+            route: format!("POST /channels/{}/messages", channel_id),
+            major_parameter: channel_id.to_string()
+        };
+
+        let url = format!("{}/channels/{}/messages", DISCORD_API_URL, channel_id);
+        let response = self.send(Method::POST, &url, route, Some(message), true).await?;
+
+        return read_json(response).await;
+    }
+
+    /**
+     * Deletes a message from a channel as the bot. Needs a bot token.
+     */
+    pub async fn delete_channel_message(&self, channel_id: &str, message_id: &str) -> Result<(), DiscordRestError> {
+        let route = Route {
+            route: format!("DELETE /channels/{}/messages/:message_id", channel_id),
+            major_parameter: channel_id.to_string()
+        };
+
+        let url = format!("{}/channels/{}/messages/{}", DISCORD_API_URL, channel_id, message_id);
+        self.send::<MessagePayload>(Method::DELETE, &url, route, None, true).await?;
+
+        return Ok(());
+    }
+
+    /**
+     * Opens (or finds the existing) DM channel between the bot and the user, which messages can then be posted to
+     * with create_channel_message. Needs a bot token.
+     */
+    pub async fn create_dm_channel(&self, recipient_id: &str) -> Result<DiscordChannel, DiscordRestError> {
+        let route = Route {
+            route: "POST /users/@me/channels".to_string(),
+            major_parameter: "".to_string()
+        };
+
+        let url = format!("{}/users/@me/channels", DISCORD_API_URL);
+        let payload = CreateDmPayload {
+            recipient_id: recipient_id.to_string()
+        };
+        let response = self.send(Method::POST, &url, route, Some(&payload), true).await?;
+
+        return read_json(response).await;
+    }
+
+    async fn send<T: Serialize>(
+        &self,
+        method: Method,
+        url: &str,
+        route: Route,
+        body: Option<&T>,
+        needs_bot_token: bool) -> Result<Response, DiscordRestError> {
+
+        let body_json = match body {
+            None => None,
+            Some(payload) => Some(serde_json::to_string(payload).map_err(|err| DiscordRestError::Serialization(err.to_string()))?)
+        };
+
+        let mut attempt: u32 = 1;
+
+        loop {
+            self.wait_for_bucket(&route).await;
+
+            let mut request = self.http_client
+                .request(method.clone(), url)
+                .header("Content-Type", "application/json");
+
+            if needs_bot_token {
+                let token = self.bot_token.as_ref().ok_or(DiscordRestError::MissingBotToken)?;
+                request = request.header("Authorization", format!("Bot {}", token));
+            }
+
+            if let Some(json) = &body_json {
+                request = request.body(json.to_string());
+            }
+
+            // Webhook URLs contain the interaction token, so it's left out of the error
+            let response = request.send().await.map_err(|err| DiscordRestError::Request(err.without_url().to_string()))?;
+            self.update_bucket(&route, &response).await;
+
+            let status = response.status();
+
+            if status.is_success() {
+                return Ok(response);
+            }
+
+            if status.as_u16() == 429 {
+                let retry_after = get_retry_after(&response);
+                println!("Rate limited by Discord on {} (attempt {}), retrying after {:?}", route.route, attempt, retry_after);
+
+                if attempt >= MAX_ATTEMPTS {
+                    return Err(DiscordRestError::RateLimited { retry_after: retry_after });
+                }
+
+                sleep(retry_after).await;
+            } else if status.is_server_error() && attempt < MAX_ATTEMPTS {
+                let backoff = Duration::from_millis(SERVER_ERROR_BASE_DELAY_MILLIS * 2u64.pow(attempt - 1));
+                println!("Discord responded with {} on {} (attempt {}), retrying after {:?}", status, route.route, attempt, backoff);
+                sleep(backoff).await;
+            } else {
+                let body = response.text().await.unwrap_or("".to_string());
+
+                return Err(DiscordRestError::Http {
+                    status: status.as_u16(),
+                    body: body
+                });
+            }
+
+            attempt = attempt + 1;
+        }
+    }
+
+    /**
+     * Waits until the bucket the route belongs to has requests remaining, if we've used them all up
+     */
+    async fn wait_for_bucket(&self, route: &Route) {
+        let reset_at = {
+            let rate_limits = self.rate_limits.lock().await;
+
+            rate_limits.route_buckets.get(&route.route)
+                .and_then(|bucket| rate_limits.buckets.get(&bucket_key(bucket, route)))
+                .filter(|state| state.remaining == 0 && state.reset_at > Instant::now())
+                .map(|state| state.reset_at)
+        };
+
+        if let Some(reset_at) = reset_at {
+            println!("Waiting for Discord rate limit bucket for {} to reset", route.route);
+            sleep_until(reset_at).await;
+        }
+    }
+
+    async fn update_bucket(&self, route: &Route, response: &Response) {
+        let headers = response.headers();
+        let header_str = |name: &str| headers.get(name).and_then(|value| value.to_str().ok()).map(|value| value.to_string());
+
+        let bucket = header_str("X-RateLimit-Bucket");
+        let remaining = header_str("X-RateLimit-Remaining").and_then(|value| value.parse::<u64>().ok());
+        let reset_after = header_str("X-RateLimit-Reset-After").and_then(|value| value.parse::<f64>().ok());
+
+        if let (Some(bucket), Some(remaining), Some(reset_after)) = (bucket, remaining, reset_after) {
+            let mut rate_limits = self.rate_limits.lock().await;
+            let key = bucket_key(&bucket, route);
+
+            // Each interaction's webhook gets its own buckets, so forget the ones that have reset to keep a warm
+            // Lambda from collecting them forever
+            let now = Instant::now();
+            rate_limits.buckets.retain(|_, state| state.reset_at > now);
+
+            rate_limits.route_buckets.insert(route.route.to_string(), bucket);
+            rate_limits.buckets.insert(key, BucketState {
+                remaining: remaining,
+                reset_at: Instant::now() + Duration::from_secs_f64(reset_after)
+            });
+        }
+    }
+}
+
+fn bucket_key(bucket: &str, route: &Route) -> String {
+    return format!("{}:{}", bucket, route.major_parameter);
+}
+
+/**
+ * How long Discord told us to wait before retrying a rate limited request. The Retry-After header is in whole
+ * seconds, so we prefer the more precise reset header if there is one.
+ */
+fn get_retry_after(response: &Response) -> Duration {
+    let headers = response.headers();
+
+    let reset_after = headers.get("X-RateLimit-Reset-After")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<f64>().ok());
+
+    let retry_after = headers.get("Retry-After")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<f64>().ok());
+
+    return reset_after.or(retry_after).map(Duration::from_secs_f64).unwrap_or(Duration::from_secs(1));
+}
+
+async fn read_json<T: serde::de::DeserializeOwned>(response: Response) -> Result<T, DiscordRestError> {
+    return response.json::<T>().await.map_err(|err| DiscordRestError::Serialization(err.without_url().to_string()));
+}
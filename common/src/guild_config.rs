@@ -0,0 +1,128 @@
+use crate::storage::{BotStorage, StorageError, StoredItem, StoredValue};
+use std::collections::HashMap;
+
+/**
+ * Each Discord server's settings are kept in this partition, keyed by guild ID
+ */
+const GUILD_CONFIG_PARTITION: &str = "guildConfig";
+
+/**
+ * Which rank changes of the server's linked members are announced
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RankAnnouncementThreshold {
+    /**
+     * Only moving up or down a tier
+     */
+    Tier,
+
+    /**
+     * Moving up or down a tier or division, and winning or losing a promotion series
+     */
+    Division
+}
+
+impl Default for RankAnnouncementThreshold {
+    fn default() -> Self {
+        return RankAnnouncementThreshold::Division;
+    }
+}
+
+impl RankAnnouncementThreshold {
+    pub fn from_name(name: &str) -> Option<RankAnnouncementThreshold> {
+        return match name {
+            "tier" => Some(RankAnnouncementThreshold::Tier),
+            "division" => Some(RankAnnouncementThreshold::Division),
+            _ => None
+        };
+    }
+
+    pub fn name(&self) -> &'static str {
+        return match self {
+            RankAnnouncementThreshold::Tier => "tier",
+            RankAnnouncementThreshold::Division => "division"
+        };
+    }
+}
+
+/**
+ * A Discord server's settings for the bot. A server that has never changed them gets the defaults.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct GuildConfig {
+    pub guild_id: String,
+
+    /**
+     * The channel the weekly digest is posted to, if the server has opted in to it with /digest
+     */
+    pub digest_channel_id: Option<String>,
+
+    /**
+     * The channel members' rank changes are announced in, if the server has turned announcements on with
+     * /announcements
+     */
+    pub rank_announcement_channel_id: Option<String>,
+    pub rank_announcement_threshold: RankAnnouncementThreshold,
+
+    /**
+     * Also announce a member gaining or losing at least this much LP since their last announcement, if set
+     */
+    pub rank_announcement_min_league_points: Option<u64>
+}
+
+pub async fn get_guild_config(storage: &dyn BotStorage, guild_id: &str) -> Result<GuildConfig, StorageError> {
+    let item = storage.get_item(GUILD_CONFIG_PARTITION, guild_id).await?;
+
+    return Ok(match item {
+        None => GuildConfig {
+            guild_id: guild_id.to_string(),
+            ..Default::default()
+        },
+        Some(item) => to_guild_config(&item)
+    });
+}
+
+pub async fn store_guild_config(storage: &dyn BotStorage, config: &GuildConfig) -> Result<(), StorageError> {
+    let mut attributes: HashMap<String, StoredValue> = HashMap::new();
+
+    if let Some(channel_id) = &config.digest_channel_id {
+        attributes.insert("digest_channel_id".to_string(), StoredValue::S(channel_id.to_string()));
+    }
+
+    if let Some(channel_id) = &config.rank_announcement_channel_id {
+        attributes.insert("rank_announcement_channel_id".to_string(), StoredValue::S(channel_id.to_string()));
+    }
+
+    attributes.insert("rank_announcement_threshold".to_string(), StoredValue::S(config.rank_announcement_threshold.name().to_string()));
+
+    if let Some(min_league_points) = config.rank_announcement_min_league_points {
+        attributes.insert("rank_announcement_min_league_points".to_string(), StoredValue::N(min_league_points as i64));
+    }
+
+    return storage.put_item(StoredItem {
+        partition_key: GUILD_CONFIG_PARTITION.to_string(),
+        sort_key: config.guild_id.to_string(),
+        attributes: attributes
+    }).await;
+}
+
+/**
+ * The settings of every server that has changed them
+ */
+pub async fn get_guild_configs(storage: &dyn BotStorage) -> Result<Vec<GuildConfig>, StorageError> {
+    let items = storage.query(GUILD_CONFIG_PARTITION, None).await?;
+
+    return Ok(items.iter().map(to_guild_config).collect());
+}
+
+fn to_guild_config(item: &StoredItem) -> GuildConfig {
+    return GuildConfig {
+        guild_id: item.sort_key.to_string(),
+        digest_channel_id: item.get_s("digest_channel_id").map(|channel_id| channel_id.to_string()),
+        rank_announcement_channel_id: item.get_s("rank_announcement_channel_id").map(|channel_id| channel_id.to_string()),
+        rank_announcement_threshold: item.get_s("rank_announcement_threshold")
+            .and_then(RankAnnouncementThreshold::from_name)
+            .unwrap_or_default(),
+        rank_announcement_min_league_points: item.get_n("rank_announcement_min_league_points").map(|league_points| league_points as u64)
+    };
+}
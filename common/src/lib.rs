@@ -1,2 +1,12 @@
+pub mod date_range;
 pub mod discord_bot_types;
-pub mod search_history;
\ No newline at end of file
+pub mod discord_rest;
+pub mod guild_config;
+pub mod linked_accounts;
+pub mod match_tracking;
+pub mod playtime_budgets;
+pub mod player_stats;
+pub mod rank_announcements;
+pub mod rank_history;
+pub mod search_history;
+pub mod storage;
\ No newline at end of file
@@ -0,0 +1,79 @@
+use crate::storage::{BotStorage, StorageError, StoredItem, StoredValue};
+use std::collections::HashMap;
+
+/**
+ * Every Discord server that has had an account linked in it is kept in this partition, keyed by guild ID
+ */
+const LINKED_GUILDS_PARTITION: &str = "linkedGuilds";
+
+/**
+ * A Discord server member's League account, from /link
+ */
+#[derive(Debug, Clone)]
+pub struct LinkedAccount {
+    pub discord_user_id: String,
+    pub puuid: String,
+    pub player_name: String
+}
+
+/**
+ * Mentions the member with their League name, e.g. '<@1234> (Bob#EUW)'. Messages that allow no mentions show
+ * this without notifying anyone.
+ */
+pub fn format_member(discord_user_id: &str, player_name: &str) -> String {
+    return format!("<@{}> ({})", discord_user_id, player_name);
+}
+
+/**
+ * Links the Discord user to the League account in the given Discord server, replacing any account they had linked
+ * there before
+ */
+pub async fn link_account(storage: &dyn BotStorage, guild_id: &str, discord_user_id: &str, puuid: &str, player_name: &str) -> Result<(), StorageError> {
+    let mut attributes: HashMap<String, StoredValue> = HashMap::new();
+    attributes.insert("puuid".to_string(), StoredValue::S(puuid.to_string()));
+    attributes.insert("player_name".to_string(), StoredValue::S(player_name.to_string()));
+
+    storage.put_item(StoredItem {
+        partition_key: linked_accounts_partition(guild_id),
+        sort_key: discord_user_id.to_string(),
+        attributes: attributes
+    }).await?;
+
+    return storage.put_item(StoredItem {
+        partition_key: LINKED_GUILDS_PARTITION.to_string(),
+        sort_key: guild_id.to_string(),
+        attributes: HashMap::new()
+    }).await;
+}
+
+pub async fn unlink_account(storage: &dyn BotStorage, guild_id: &str, discord_user_id: &str) -> Result<(), StorageError> {
+    return storage.delete_item(&linked_accounts_partition(guild_id), discord_user_id).await;
+}
+
+/**
+ * Every account linked by members of the given Discord server
+ */
+pub async fn get_linked_accounts(storage: &dyn BotStorage, guild_id: &str) -> Result<Vec<LinkedAccount>, StorageError> {
+    let items = storage.query(&linked_accounts_partition(guild_id), None).await?;
+
+    return Ok(items.iter().filter_map(|item| {
+        Some(LinkedAccount {
+            discord_user_id: item.sort_key.to_string(),
+            puuid: item.get_s("puuid")?.to_string(),
+            player_name: item.get_s("player_name")?.to_string()
+        })
+    }).collect());
+}
+
+/**
+ * The IDs of the Discord servers that have had an account linked in them
+ */
+pub async fn get_linked_guilds(storage: &dyn BotStorage) -> Result<Vec<String>, StorageError> {
+    let items = storage.query(LINKED_GUILDS_PARTITION, None).await?;
+
+    return Ok(items.iter().map(|item| item.sort_key.to_string()).collect());
+}
+
+fn linked_accounts_partition(guild_id: &str) -> String {
+    return format!("linkedAccounts#{}", guild_id);
+}
@@ -0,0 +1,81 @@
+use crate::storage::{BotStorage, StorageError, StoredItem, StoredValue};
+use std::collections::HashMap;
+
+/**
+ * Which channels get a post when each player finishes a game, keyed by PUUID and channel ID
+ */
+const MATCH_TRACKED_PLAYERS_PARTITION: &str = "matchTrackedPlayers";
+
+/**
+ * The most recent game we've seen each tracked player finish, keyed by PUUID
+ */
+const LAST_SEEN_MATCHES_PARTITION: &str = "lastSeenMatches";
+
+/**
+ * A player whose finished games are posted to a channel, from /track
+ */
+#[derive(Debug, Clone)]
+pub struct MatchTrackedPlayer {
+    pub puuid: String,
+    pub player_name: String,
+    pub guild_id: String,
+    pub channel_id: String
+}
+
+pub async fn track_matches(storage: &dyn BotStorage, player: &MatchTrackedPlayer) -> Result<(), StorageError> {
+    let mut attributes: HashMap<String, StoredValue> = HashMap::new();
+    attributes.insert("puuid".to_string(), StoredValue::S(player.puuid.to_string()));
+    attributes.insert("player_name".to_string(), StoredValue::S(player.player_name.to_string()));
+    attributes.insert("guild_id".to_string(), StoredValue::S(player.guild_id.to_string()));
+    attributes.insert("channel_id".to_string(), StoredValue::S(player.channel_id.to_string()));
+
+    return storage.put_item(StoredItem {
+        partition_key: MATCH_TRACKED_PLAYERS_PARTITION.to_string(),
+        sort_key: tracked_player_sort_key(&player.puuid, &player.channel_id),
+        attributes: attributes
+    }).await;
+}
+
+pub async fn untrack_matches(storage: &dyn BotStorage, puuid: &str, channel_id: &str) -> Result<(), StorageError> {
+    return storage.delete_item(MATCH_TRACKED_PLAYERS_PARTITION, &tracked_player_sort_key(puuid, channel_id)).await;
+}
+
+/**
+ * Every player being tracked, once for each channel they're tracked in
+ */
+pub async fn get_match_tracked_players(storage: &dyn BotStorage) -> Result<Vec<MatchTrackedPlayer>, StorageError> {
+    let items = storage.query(MATCH_TRACKED_PLAYERS_PARTITION, None).await?;
+
+    return Ok(items.iter().filter_map(|item| {
+        Some(MatchTrackedPlayer {
+            puuid: item.get_s("puuid")?.to_string(),
+            player_name: item.get_s("player_name")?.to_string(),
+            guild_id: item.get_s("guild_id")?.to_string(),
+            channel_id: item.get_s("channel_id")?.to_string()
+        })
+    }).collect());
+}
+
+/**
+ * The match ID of the most recent game we've seen the player finish, if we've looked before
+ */
+pub async fn get_last_seen_match(storage: &dyn BotStorage, puuid: &str) -> Result<Option<String>, StorageError> {
+    let item = storage.get_item(LAST_SEEN_MATCHES_PARTITION, puuid).await?;
+
+    return Ok(item.and_then(|item| item.get_s("match_id").map(|match_id| match_id.to_string())));
+}
+
+pub async fn store_last_seen_match(storage: &dyn BotStorage, puuid: &str, match_id: &str) -> Result<(), StorageError> {
+    let mut attributes: HashMap<String, StoredValue> = HashMap::new();
+    attributes.insert("match_id".to_string(), StoredValue::S(match_id.to_string()));
+
+    return storage.put_item(StoredItem {
+        partition_key: LAST_SEEN_MATCHES_PARTITION.to_string(),
+        sort_key: puuid.to_string(),
+        attributes: attributes
+    }).await;
+}
+
+fn tracked_player_sort_key(puuid: &str, channel_id: &str) -> String {
+    return format!("{}#{}", puuid, channel_id);
+}
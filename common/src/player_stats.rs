@@ -0,0 +1,171 @@
+use crate::storage::{BotStorage, StorageError, StoredItem, StoredValue};
+use lol::models::UserGameSummary;
+use std::collections::HashMap;
+
+/**
+ * Each player's stats for the last week are kept in this partition, keyed by PUUID
+ */
+const WEEKLY_STATS_PARTITION: &str = "weeklyPlayerStats";
+
+/**
+ * How much a player played over the 7 days before the stats were updated
+ */
+#[derive(Debug, Clone)]
+pub struct WeeklyStats {
+    pub puuid: String,
+    pub games: u64,
+    pub wins: u64,
+    pub played_for_millis: u64,
+
+    /**
+     * When the stats were worked out, in seconds since the epoch
+     */
+    pub updated_at: u64
+}
+
+pub async fn store_weekly_stats(storage: &dyn BotStorage, stats: &WeeklyStats) -> Result<(), StorageError> {
+    let mut attributes: HashMap<String, StoredValue> = HashMap::new();
+    attributes.insert("games".to_string(), StoredValue::N(stats.games as i64));
+    attributes.insert("wins".to_string(), StoredValue::N(stats.wins as i64));
+    attributes.insert("played_for_millis".to_string(), StoredValue::N(stats.played_for_millis as i64));
+    attributes.insert("updated_at".to_string(), StoredValue::N(stats.updated_at as i64));
+
+    return storage.put_item(StoredItem {
+        partition_key: WEEKLY_STATS_PARTITION.to_string(),
+        sort_key: stats.puuid.to_string(),
+        attributes: attributes
+    }).await;
+}
+
+pub async fn get_weekly_stats(storage: &dyn BotStorage, puuid: &str) -> Result<Option<WeeklyStats>, StorageError> {
+    let item = storage.get_item(WEEKLY_STATS_PARTITION, puuid).await?;
+
+    return Ok(item.and_then(|item| {
+        Some(WeeklyStats {
+            puuid: item.sort_key.to_string(),
+            games: item.get_n("games")? as u64,
+            wins: item.get_n("wins")? as u64,
+            played_for_millis: item.get_n("played_for_millis")? as u64,
+            updated_at: item.get_n("updated_at")? as u64
+        })
+    }));
+}
+
+/**
+ * How a player did in one of their games, kept so refreshes only need to fetch the games they haven't seen yet
+ */
+#[derive(Debug, Clone)]
+pub struct PlayedGame {
+    pub match_id: String,
+    pub champion_name: String,
+    pub win: bool,
+    pub kills: u64,
+    pub deaths: u64,
+    pub assists: u64,
+    pub duration_millis: u64,
+
+    /**
+     * When the game ended, in milliseconds since the epoch
+     */
+    pub game_end_timestamp: u64
+}
+
+impl PlayedGame {
+    pub fn from_summary(game: &UserGameSummary) -> PlayedGame {
+        return PlayedGame {
+            match_id: game.match_id.to_string(),
+            champion_name: game.participant.championName.to_string(),
+            win: game.participant.win,
+            kills: game.participant.kills,
+            deaths: game.participant.deaths,
+            assists: game.participant.assists,
+            duration_millis: game.game_duration_millis,
+            game_end_timestamp: game.game_end_timestamp
+        };
+    }
+}
+
+pub async fn store_played_game(storage: &dyn BotStorage, puuid: &str, game: &PlayedGame) -> Result<(), StorageError> {
+    let mut attributes: HashMap<String, StoredValue> = HashMap::new();
+    attributes.insert("champion_name".to_string(), StoredValue::S(game.champion_name.to_string()));
+    attributes.insert("win".to_string(), StoredValue::N(game.win as i64));
+    attributes.insert("kills".to_string(), StoredValue::N(game.kills as i64));
+    attributes.insert("deaths".to_string(), StoredValue::N(game.deaths as i64));
+    attributes.insert("assists".to_string(), StoredValue::N(game.assists as i64));
+    attributes.insert("duration_millis".to_string(), StoredValue::N(game.duration_millis as i64));
+    attributes.insert("game_end_timestamp".to_string(), StoredValue::N(game.game_end_timestamp as i64));
+
+    return storage.put_item(StoredItem {
+        partition_key: played_games_partition(puuid),
+        sort_key: game.match_id.to_string(),
+        attributes: attributes
+    }).await;
+}
+
+/**
+ * Returns every game stored for the player, including ones older than a week that haven't been pruned yet
+ */
+pub async fn get_played_games(storage: &dyn BotStorage, puuid: &str) -> Result<Vec<PlayedGame>, StorageError> {
+    let items = storage.query(&played_games_partition(puuid), None).await?;
+
+    return Ok(items.iter().filter_map(to_played_game).collect());
+}
+
+pub async fn delete_played_game(storage: &dyn BotStorage, puuid: &str, match_id: &str) -> Result<(), StorageError> {
+    return storage.delete_item(&played_games_partition(puuid), match_id).await;
+}
+
+fn to_played_game(item: &StoredItem) -> Option<PlayedGame> {
+    return Some(PlayedGame {
+        match_id: item.sort_key.to_string(),
+        champion_name: item.get_s("champion_name")?.to_string(),
+        win: item.get_n("win")? != 0,
+        kills: item.get_n("kills")? as u64,
+        deaths: item.get_n("deaths")? as u64,
+        assists: item.get_n("assists")? as u64,
+        duration_millis: item.get_n("duration_millis")? as u64,
+        game_end_timestamp: item.get_n("game_end_timestamp")? as u64
+    });
+}
+
+fn played_games_partition(puuid: &str) -> String {
+    return format!("playedGames#{}", puuid);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::in_memory::InMemoryStorage;
+
+    fn game(match_id: &str, win: bool) -> PlayedGame {
+        return PlayedGame {
+            match_id: match_id.to_string(),
+            champion_name: "Ahri".to_string(),
+            win: win,
+            kills: 7,
+            deaths: 0,
+            assists: 12,
+            duration_millis: 1_800_000,
+            game_end_timestamp: 1_700_000_000_000
+        };
+    }
+
+    #[tokio::test]
+    async fn played_games_round_trip_per_player() {
+        let storage = InMemoryStorage::new();
+        store_played_game(&storage, "first", &game("EUW1_1", true)).await.unwrap();
+        store_played_game(&storage, "first", &game("EUW1_2", false)).await.unwrap();
+        store_played_game(&storage, "second", &game("EUW1_3", true)).await.unwrap();
+
+        let games = get_played_games(&storage, "first").await.unwrap();
+        assert_eq!(games.iter().map(|game| game.match_id.as_str()).collect::<Vec<_>>(), vec!["EUW1_1", "EUW1_2"]);
+        assert!(games[0].win);
+        assert!(!games[1].win);
+        assert_eq!(games[0].deaths, 0);
+        assert_eq!(games[0].champion_name, "Ahri");
+
+        delete_played_game(&storage, "first", "EUW1_1").await.unwrap();
+        assert_eq!(get_played_games(&storage, "first").await.unwrap().len(), 1);
+        assert_eq!(get_played_games(&storage, "second").await.unwrap().len(), 1);
+    }
+}
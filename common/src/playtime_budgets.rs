@@ -0,0 +1,188 @@
+use crate::storage::{BotStorage, StorageError, StoredItem, StoredValue};
+use chrono::{Datelike, Duration, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/**
+ * Each Discord user's playtime budget is kept in this partition, keyed by their Discord user ID
+ */
+const PLAYTIME_BUDGETS_PARTITION: &str = "playtimeBudgets";
+
+/**
+ * How often a playtime budget starts again. Days and weeks (from Monday) are in UTC.
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetPeriod {
+    Daily,
+    Weekly
+}
+
+impl BudgetPeriod {
+    pub fn from_name(name: &str) -> Option<BudgetPeriod> {
+        return match name {
+            "daily" => Some(BudgetPeriod::Daily),
+            "weekly" => Some(BudgetPeriod::Weekly),
+            _ => None
+        };
+    }
+
+    pub fn name(&self) -> &'static str {
+        return match self {
+            BudgetPeriod::Daily => "daily",
+            BudgetPeriod::Weekly => "weekly"
+        };
+    }
+
+    /**
+     * e.g. 'today'
+     */
+    pub fn description(&self) -> &'static str {
+        return match self {
+            BudgetPeriod::Daily => "today",
+            BudgetPeriod::Weekly => "this week"
+        };
+    }
+
+    /**
+     * When the period that the given time is in started, in seconds since the epoch
+     */
+    pub fn start(&self, now: u64) -> u64 {
+        let today = Utc.timestamp(now as i64, 0).date();
+
+        let start = match self {
+            BudgetPeriod::Daily => today,
+            BudgetPeriod::Weekly => today - Duration::days(today.weekday().num_days_from_monday() as i64)
+        };
+
+        return start.and_hms(0, 0, 0).timestamp() as u64;
+    }
+
+    /**
+     * The period the given time is in, up to that time
+     */
+    pub fn range(&self, now: u64) -> crate::date_range::DateRange {
+        return crate::date_range::DateRange {
+            start: self.start(now),
+            end: now,
+            description: self.description().to_string()
+        };
+    }
+}
+
+/**
+ * How long a Discord user has said they want to play for each day or week, from /budget
+ */
+#[derive(Debug, Clone)]
+pub struct PlaytimeBudget {
+    pub discord_user_id: String,
+    pub puuid: String,
+    pub player_name: String,
+    pub period: BudgetPeriod,
+    pub limit_millis: u64,
+
+    /**
+     * When the budget was set, in milliseconds since the epoch. A check only records what it found if the budget
+     * hasn't been set again (or cleared) since the check read it.
+     */
+    pub set_at_millis: u64,
+
+    /**
+     * The channel to warn the user in when they go over their budget, or None to send them a DM
+     */
+    pub channel_id: Option<String>,
+
+    /**
+     * The start of the last period we warned the user in, so they're only warned once each period
+     */
+    pub warned_period_start: Option<u64>,
+
+    /**
+     * The latest game we've counted towards the budget, so the games only need counting again once they play another
+     */
+    pub last_checked_match_id: Option<String>
+}
+
+pub async fn store_budget(storage: &dyn BotStorage, budget: &PlaytimeBudget) -> Result<(), StorageError> {
+    let mut attributes: HashMap<String, StoredValue> = HashMap::new();
+    attributes.insert("puuid".to_string(), StoredValue::S(budget.puuid.to_string()));
+    attributes.insert("player_name".to_string(), StoredValue::S(budget.player_name.to_string()));
+    attributes.insert("period".to_string(), StoredValue::S(budget.period.name().to_string()));
+    attributes.insert("limit_millis".to_string(), StoredValue::N(budget.limit_millis as i64));
+    attributes.insert("set_at_millis".to_string(), StoredValue::N(budget.set_at_millis as i64));
+
+    if let Some(channel_id) = &budget.channel_id {
+        attributes.insert("channel_id".to_string(), StoredValue::S(channel_id.to_string()));
+    }
+
+    if let Some(warned_period_start) = budget.warned_period_start {
+        attributes.insert("warned_period_start".to_string(), StoredValue::N(warned_period_start as i64));
+    }
+
+    if let Some(match_id) = &budget.last_checked_match_id {
+        attributes.insert("last_checked_match_id".to_string(), StoredValue::S(match_id.to_string()));
+    }
+
+    return storage.put_item(StoredItem {
+        partition_key: PLAYTIME_BUDGETS_PARTITION.to_string(),
+        sort_key: budget.discord_user_id.to_string(),
+        attributes: attributes
+    }).await;
+}
+
+/**
+ * Records the latest game a check counted, and the period the user was warned in if they were, without touching the
+ * rest of the budget. Returns false without recording anything if the budget was set again or cleared since the
+ * check read it.
+ */
+pub async fn record_budget_check(
+    storage: &dyn BotStorage,
+    budget: &PlaytimeBudget,
+    last_checked_match_id: &str,
+    warned_period_start: Option<u64>) -> Result<bool, StorageError> {
+
+    let mut attributes: HashMap<String, StoredValue> = HashMap::new();
+    attributes.insert("last_checked_match_id".to_string(), StoredValue::S(last_checked_match_id.to_string()));
+
+    if let Some(warned_period_start) = warned_period_start {
+        attributes.insert("warned_period_start".to_string(), StoredValue::N(warned_period_start as i64));
+    }
+
+    return storage.update_if(
+        PLAYTIME_BUDGETS_PARTITION,
+        &budget.discord_user_id,
+        "set_at_millis",
+        StoredValue::N(budget.set_at_millis as i64),
+        attributes
+    ).await;
+}
+
+pub async fn get_budget(storage: &dyn BotStorage, discord_user_id: &str) -> Result<Option<PlaytimeBudget>, StorageError> {
+    let item = storage.get_item(PLAYTIME_BUDGETS_PARTITION, discord_user_id).await?;
+
+    return Ok(item.and_then(|item| to_budget(&item)));
+}
+
+pub async fn clear_budget(storage: &dyn BotStorage, discord_user_id: &str) -> Result<(), StorageError> {
+    return storage.delete_item(PLAYTIME_BUDGETS_PARTITION, discord_user_id).await;
+}
+
+pub async fn get_budgets(storage: &dyn BotStorage) -> Result<Vec<PlaytimeBudget>, StorageError> {
+    let items = storage.query(PLAYTIME_BUDGETS_PARTITION, None).await?;
+
+    return Ok(items.iter().filter_map(to_budget).collect());
+}
+
+fn to_budget(item: &StoredItem) -> Option<PlaytimeBudget> {
+    return Some(PlaytimeBudget {
+        discord_user_id: item.sort_key.to_string(),
+        puuid: item.get_s("puuid")?.to_string(),
+        player_name: item.get_s("player_name")?.to_string(),
+        period: BudgetPeriod::from_name(item.get_s("period")?)?,
+        limit_millis: item.get_n("limit_millis")? as u64,
+        set_at_millis: item.get_n("set_at_millis")? as u64,
+        channel_id: item.get_s("channel_id").map(|channel_id| channel_id.to_string()),
+        warned_period_start: item.get_n("warned_period_start").map(|start| start as u64),
+        last_checked_match_id: item.get_s("last_checked_match_id").map(|match_id| match_id.to_string())
+    });
+}
@@ -0,0 +1,48 @@
+use crate::storage::{BotStorage, StorageError, StoredItem, StoredValue};
+use lol::models::LeagueEntry;
+use std::collections::HashMap;
+
+/**
+ * The rank each member had when we last announced it (or first saw it) in a server
+ */
+#[derive(Debug, Clone)]
+pub struct AnnouncedRank {
+    pub puuid: String,
+    pub entry: LeagueEntry
+}
+
+pub async fn get_announced_rank(storage: &dyn BotStorage, guild_id: &str, puuid: &str, queue_type: &str) -> Result<Option<AnnouncedRank>, StorageError> {
+    let item = storage.get_item(&announced_ranks_partition(guild_id), &announced_rank_sort_key(puuid, queue_type)).await?;
+
+    return Ok(item.and_then(|item| {
+        let entry = serde_json::from_str::<LeagueEntry>(item.get_s("entry")?).ok()?;
+
+        Some(AnnouncedRank {
+            puuid: puuid.to_string(),
+            entry: entry
+        })
+    }));
+}
+
+pub async fn store_announced_rank(storage: &dyn BotStorage, guild_id: &str, puuid: &str, entry: &LeagueEntry) -> Result<(), StorageError> {
+    let entry_json = serde_json::to_string(entry).map_err(|err| StorageError {
+        description: format!("Could not write league entry to JSON: {}", err)
+    })?;
+
+    let mut attributes: HashMap<String, StoredValue> = HashMap::new();
+    attributes.insert("entry".to_string(), StoredValue::S(entry_json));
+
+    return storage.put_item(StoredItem {
+        partition_key: announced_ranks_partition(guild_id),
+        sort_key: announced_rank_sort_key(puuid, &entry.queueType),
+        attributes: attributes
+    }).await;
+}
+
+fn announced_ranks_partition(guild_id: &str) -> String {
+    return format!("announcedRanks#{}", guild_id);
+}
+
+fn announced_rank_sort_key(puuid: &str, queue_type: &str) -> String {
+    return format!("{}#{}", puuid, queue_type);
+}
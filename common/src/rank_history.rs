@@ -0,0 +1,157 @@
+use crate::storage::{BotStorage, StorageError, StoredItem, StoredValue};
+use lol::models::LeagueEntry;
+use std::collections::HashMap;
+
+/**
+ * Players whose rank is snapshotted on a schedule are kept in this partition, keyed by PUUID
+ */
+const TRACKED_PLAYERS_PARTITION: &str = "rankTrackedPlayers";
+
+#[derive(Debug, Clone)]
+pub struct RankSnapshot {
+    /**
+     * When the snapshot was taken, in seconds since the epoch
+     */
+    pub timestamp: u64,
+    pub entry: LeagueEntry
+}
+
+#[derive(Debug, Clone)]
+pub struct TrackedPlayer {
+    pub puuid: String,
+    pub summoner_id: String,
+    pub player_name: String
+}
+
+/**
+ * Remembers the player so that the scheduled job keeps snapshotting their rank
+ */
+pub async fn track_player(storage: &dyn BotStorage, puuid: &str, summoner_id: &str, player_name: &str) -> Result<(), StorageError> {
+    let mut attributes: HashMap<String, StoredValue> = HashMap::new();
+    attributes.insert("summoner_id".to_string(), StoredValue::S(summoner_id.to_string()));
+    attributes.insert("player_name".to_string(), StoredValue::S(player_name.to_string()));
+
+    return storage.put_item(StoredItem {
+        partition_key: TRACKED_PLAYERS_PARTITION.to_string(),
+        sort_key: puuid.to_string(),
+        attributes: attributes
+    }).await;
+}
+
+pub async fn get_tracked_players(storage: &dyn BotStorage) -> Result<Vec<TrackedPlayer>, StorageError> {
+    let items = storage.query(TRACKED_PLAYERS_PARTITION, None).await?;
+
+    return Ok(items.iter().filter_map(|item| {
+        Some(TrackedPlayer {
+            puuid: item.sort_key.to_string(),
+            summoner_id: item.get_s("summoner_id")?.to_string(),
+            player_name: item.get_s("player_name")?.to_string()
+        })
+    }).collect());
+}
+
+/**
+ * Stores a snapshot of each of the player's queue rankings, skipping any queue where nothing has changed since the
+ * last snapshot
+ */
+pub async fn store_rank_snapshots(storage: &dyn BotStorage, puuid: &str, entries: &Vec<LeagueEntry>) -> Result<(), StorageError> {
+    let now = crate::date_range::now_seconds()
+        .map_err(|err| StorageError {
+            description: format!("Could not get the time for the snapshot: {}", err)
+        })?;
+
+    for entry in entries.iter() {
+        let previous = get_latest_rank_snapshot(storage, puuid, &entry.queueType).await?;
+        let unchanged = previous.map(|last| is_same_standing(&last.entry, entry)).unwrap_or(false);
+
+        if unchanged {
+            continue;
+        }
+
+        let entry_json = serde_json::to_string(entry).map_err(|err| StorageError {
+            description: format!("Could not write league entry to JSON: {}", err)
+        })?;
+
+        let mut attributes: HashMap<String, StoredValue> = HashMap::new();
+        attributes.insert("entry".to_string(), StoredValue::S(entry_json));
+        attributes.insert("timestamp".to_string(), StoredValue::N(now as i64));
+
+        storage.put_item(StoredItem {
+            partition_key: rank_history_partition(puuid),
+            // Zero padded so that sorting by sort key sorts by time
+            sort_key: format!("{}#{:012}", entry.queueType, now),
+            attributes: attributes.clone()
+        }).await?;
+
+        // A copy of the latest snapshot lets us read the current rank without reading the whole history
+        storage.put_item(StoredItem {
+            partition_key: rank_history_partition(puuid),
+            sort_key: latest_snapshot_sort_key(&entry.queueType),
+            attributes: attributes
+        }).await?;
+    }
+
+    return Ok(());
+}
+
+/**
+ * The most recent of the player's rank snapshots for the queue
+ */
+pub async fn get_latest_rank_snapshot(storage: &dyn BotStorage, puuid: &str, queue_type: &str) -> Result<Option<RankSnapshot>, StorageError> {
+    let item = storage.get_item(&rank_history_partition(puuid), &latest_snapshot_sort_key(queue_type)).await?;
+
+    return Ok(item.and_then(|item| to_rank_snapshot(&item)));
+}
+
+/**
+ * Returns the player's rank snapshots for the queue, oldest first
+ */
+pub async fn get_rank_snapshots(storage: &dyn BotStorage, puuid: &str, queue_type: &str) -> Result<Vec<RankSnapshot>, StorageError> {
+    let prefix = format!("{}#", queue_type);
+    let items = storage.query(&rank_history_partition(puuid), Some(&prefix)).await?;
+
+    let mut snapshots: Vec<RankSnapshot> = items.iter().filter_map(to_rank_snapshot).collect();
+
+    snapshots.sort_by_key(|snapshot| snapshot.timestamp);
+
+    return Ok(snapshots);
+}
+
+/**
+ * The snapshot to compare the current rank against to see how it changed since the given time. This is the last
+ * snapshot taken before then if there is one, or otherwise the earliest one taken since.
+ */
+pub fn find_baseline_snapshot(snapshots: &Vec<RankSnapshot>, since: u64) -> Option<&RankSnapshot> {
+    let before = snapshots.iter().filter(|snapshot| snapshot.timestamp <= since).last();
+
+    return before.or(snapshots.iter().find(|snapshot| snapshot.timestamp > since));
+}
+
+fn to_rank_snapshot(item: &StoredItem) -> Option<RankSnapshot> {
+    let timestamp = item.get_n("timestamp")?;
+    let entry = serde_json::from_str::<LeagueEntry>(item.get_s("entry")?).ok()?;
+
+    return Some(RankSnapshot {
+        timestamp: timestamp as u64,
+        entry: entry
+    });
+}
+
+/**
+ * Kept out of the '<queue type>#<timestamp>' sort keys so that the latest copy isn't read back as part of the history
+ */
+fn latest_snapshot_sort_key(queue_type: &str) -> String {
+    return format!("latest#{}", queue_type);
+}
+
+fn is_same_standing(previous: &LeagueEntry, current: &LeagueEntry) -> bool {
+    return previous.tier == current.tier
+        && previous.rank == current.rank
+        && previous.leaguePoints == current.leaguePoints
+        && previous.wins == current.wins
+        && previous.losses == current.losses;
+}
+
+fn rank_history_partition(puuid: &str) -> String {
+    return format!("rankHistory#{}", puuid);
+}
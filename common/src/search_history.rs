@@ -1,5 +1,4 @@
-use aws_sdk_dynamodb::{Client, Error};
-use aws_sdk_dynamodb::model::{AttributeValue};
+use crate::storage::{BotStorage, StorageError, StoredItem, StoredValue};
 use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -11,9 +10,7 @@ pub struct SearchedDetails {
     pub last_search: u64
 }
 
-pub async fn store_search(client: &Client, discord_user_id: &str, searched_for: &str) -> Result<(), Error> {
-    let table = "grupoSillasBotTable";
-
+pub async fn store_search(storage: &dyn BotStorage, discord_user_id: &str, searched_for: &str) -> Result<(), StorageError> {
     let start = SystemTime::now();
     let since_the_epoch = start
         .duration_since(UNIX_EPOCH)
@@ -22,73 +19,28 @@ pub async fn store_search(client: &Client, discord_user_id: &str, searched_for:
 
     let seconds_since_epoch = since_the_epoch.as_secs();
 
-    let mut key_map: HashMap<std::string::String, AttributeValue> = HashMap::new();
-    key_map.insert("partitionKey".to_string(), AttributeValue::S(discord_user_id.to_string()));
-    key_map.insert("sortKey".to_string(), AttributeValue::S(searched_for.to_string()));
-
-    let mut attribute_map: HashMap<std::string::String, AttributeValue> = HashMap::new();
-    attribute_map.insert(":initial".to_string(), AttributeValue::N("1".to_string()));
-    attribute_map.insert(":num".to_string(), AttributeValue::N("1".to_string()));
-    attribute_map.insert(":now".to_string(), AttributeValue::N(seconds_since_epoch.to_string()));
+    let mut attribute_map: HashMap<String, StoredValue> = HashMap::new();
+    attribute_map.insert("last_search".to_string(), StoredValue::N(seconds_since_epoch as i64));
 
-    client
-        .update_item()
-        .table_name(table)
-        .set_key(
-            Some(key_map)
-        )
-        .update_expression("SET times = if_not_exists(times, :initial) + :num, last_search= :now")
-        .set_expression_attribute_values(Some(attribute_map))
-        .send()
-        .await?;
-   
-    // TODO use UpdateItemOutput to return the new value for the row?
-    return Ok(());
+    // TODO use the storage to return the new value for the row?
+    return storage.increment(discord_user_id, searched_for, "times", attribute_map).await;
 }
 
-pub async fn get_searches(client: &Client, discord_user_id: &str) -> Result<Vec<SearchedDetails>, Error> {
-    let table_name = "grupoSillasBotTable";
-
-    let discord_attribute_id = AttributeValue::S(discord_user_id.to_string());
-    let result = client
-        .query()
-        .table_name(table_name)
-        .key_condition_expression("#partitionKey = :discord_id")
-        .expression_attribute_names("#partitionKey", "partitionKey")
-        .expression_attribute_values(
-            ":discord_id",
-            discord_attribute_id,
-        )
-        .send()
-        .await?;
-
-    let items = result.items;
+pub async fn get_searches(storage: &dyn BotStorage, discord_user_id: &str) -> Result<Vec<SearchedDetails>, StorageError> {
+    let items = storage.query(discord_user_id, None).await?;
 
-    match items {
-        None => {
-            println!("None?");
-            return Ok(Vec::new());
-        },
-        Some(results) => {
-            println!("{:?}", results);
-
-            let result: Vec<SearchedDetails> = results.into_iter().map(|x| get_searched_details(&x)).flatten().collect();
-            return Ok(result);
-        }
-    }
+    let result: Vec<SearchedDetails> = items.iter().filter_map(get_searched_details).collect();
+    return Ok(result);
 }
 
-fn get_searched_details(map: &HashMap<String, AttributeValue>) -> Option<SearchedDetails> {
-
-    let discord_id = map.get("partitionKey").and_then(|x| x.as_s().ok() )?;
-    let username = map.get("sortKey").and_then(|x| x.as_s().ok())?;
-    let num_searches: u64 = map.get("times").and_then(|x| x.as_n().ok().and_then(|y| y.parse::<u64>().ok()))?;
-    let last_search = map.get("last_search").and_then(|x| x.as_n().ok()).and_then(|y| y.parse::<u64>().ok())?;
+fn get_searched_details(item: &StoredItem) -> Option<SearchedDetails> {
+    let num_searches = item.get_n("times")?;
+    let last_search = item.get_n("last_search")?;
 
     return Some(SearchedDetails {
-        discord_id: discord_id.to_string(),
-        searched_name: username.to_string(),
-        times: num_searches,
-        last_search: last_search
+        discord_id: item.partition_key.to_string(),
+        searched_name: item.sort_key.to_string(),
+        times: num_searches as u64,
+        last_search: last_search as u64
     })
-}
\ No newline at end of file
+}
@@ -0,0 +1,254 @@
+use async_trait::async_trait;
+use aws_config::meta::region::RegionProviderChain;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+
+pub mod dynamo;
+pub mod in_memory;
+pub mod sqlite;
+
+/**
+ * The default DynamoDB table, used if the DYNAMO_TABLE_NAME environment variable is not set
+ */
+const DEFAULT_DYNAMO_TABLE_NAME: &str = "grupoSillasBotTable";
+
+#[derive(Debug)]
+pub struct StorageError {
+    pub description: String
+}
+
+impl std::fmt::Display for StorageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.description)
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum StoredValue {
+    S(String),
+    N(i64)
+}
+
+impl StoredValue {
+    pub fn as_s(&self) -> Option<&str> {
+        match self {
+            StoredValue::S(x) => Some(x),
+            StoredValue::N(_) => None
+        }
+    }
+
+    pub fn as_n(&self) -> Option<i64> {
+        match self {
+            StoredValue::N(x) => Some(*x),
+            StoredValue::S(_) => None
+        }
+    }
+}
+
+/**
+ * A row of bot state. Every backend is laid out like the DynamoDB table: rows are grouped
+ * by a partition key and ordered within the partition by a sort key.
+ */
+#[derive(Debug, Clone)]
+pub struct StoredItem {
+    pub partition_key: String,
+    pub sort_key: String,
+    pub attributes: HashMap<String, StoredValue>
+}
+
+impl StoredItem {
+    pub fn get_s(&self, attribute: &str) -> Option<&str> {
+        self.attributes.get(attribute).and_then(|x| x.as_s())
+    }
+
+    pub fn get_n(&self, attribute: &str) -> Option<i64> {
+        self.attributes.get(attribute).and_then(|x| x.as_n())
+    }
+}
+
+#[async_trait]
+pub trait BotStorage: Send + Sync {
+    async fn get_item(&self, partition_key: &str, sort_key: &str) -> Result<Option<StoredItem>, StorageError>;
+
+    /**
+     * Inserts the item, replacing any existing item with the same partition and sort key
+     */
+    async fn put_item(&self, item: StoredItem) -> Result<(), StorageError>;
+
+    async fn delete_item(&self, partition_key: &str, sort_key: &str) -> Result<(), StorageError>;
+
+    /**
+     * Returns every item in the partition (optionally only those whose sort key starts with the given prefix),
+     * ordered by sort key
+     */
+    async fn query(&self, partition_key: &str, sort_key_prefix: Option<&str>) -> Result<Vec<StoredItem>, StorageError>;
+
+    /**
+     * Sets the given attributes on the item, leaving its other attributes alone, but only if the item exists and the
+     * condition attribute still has the expected value. Returns whether the item was updated.
+     */
+    async fn update_if(
+        &self,
+        partition_key: &str,
+        sort_key: &str,
+        condition_attribute: &str,
+        expected_value: StoredValue,
+        set_attributes: HashMap<String, StoredValue>) -> Result<bool, StorageError>;
+
+    /**
+     * Atomically adds one to the given counter attribute (creating the item if it doesn't exist) and
+     * sets the other given attributes on the item
+     */
+    async fn increment(
+        &self,
+        partition_key: &str,
+        sort_key: &str,
+        counter_attribute: &str,
+        set_attributes: HashMap<String, StoredValue>) -> Result<(), StorageError>;
+}
+
+pub enum StorageConfig {
+    DynamoDb { table_name: String },
+    Sqlite { path: String },
+    InMemory
+}
+
+/**
+ * Reads which storage backend to use from the STORAGE_BACKEND environment variable ('dynamodb', 'sqlite'
+ * or 'memory'), defaulting to DynamoDB
+ */
+pub fn storage_config_from_env() -> Result<StorageConfig, StorageError> {
+    let backend = env::var("STORAGE_BACKEND").unwrap_or("dynamodb".to_string());
+
+    match backend.as_str() {
+        "dynamodb" => Ok(StorageConfig::DynamoDb {
+            table_name: env::var("DYNAMO_TABLE_NAME").unwrap_or(DEFAULT_DYNAMO_TABLE_NAME.to_string())
+        }),
+        "sqlite" => {
+            let path = env::var("SQLITE_DATABASE_PATH").map_err(|_| StorageError {
+                description: "Missing SQLITE_DATABASE_PATH environment variable".to_string()
+            })?;
+
+            Ok(StorageConfig::Sqlite { path: path })
+        },
+        "memory" => Ok(StorageConfig::InMemory),
+        x => Err(StorageError {
+            description: format!("Unrecognised STORAGE_BACKEND {}", x)
+        })
+    }
+}
+
+pub async fn create_storage(config: StorageConfig) -> Result<Box<dyn BotStorage>, StorageError> {
+    match config {
+        StorageConfig::DynamoDb { table_name } => {
+            let region_provider = RegionProviderChain::default_provider().or_else("us-east-1");
+            let aws_config = aws_config::from_env().region(region_provider).load().await;
+            let client = aws_sdk_dynamodb::Client::new(&aws_config);
+
+            Ok(Box::new(dynamo::DynamoStorage::new(client, table_name)))
+        },
+        StorageConfig::Sqlite { path } => Ok(Box::new(sqlite::SqliteStorage::open(&path)?)),
+        StorageConfig::InMemory => Ok(Box::new(in_memory::InMemoryStorage::new()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /**
+     * Every backend that can run without AWS, each starting empty
+     */
+    fn backends() -> Vec<(&'static str, Box<dyn BotStorage>)> {
+        return vec![
+            ("in memory", Box::new(in_memory::InMemoryStorage::new())),
+            ("sqlite", Box::new(sqlite::SqliteStorage::open(":memory:").unwrap()))
+        ];
+    }
+
+    fn item(partition_key: &str, sort_key: &str, attributes: Vec<(&str, StoredValue)>) -> StoredItem {
+        return StoredItem {
+            partition_key: partition_key.to_string(),
+            sort_key: sort_key.to_string(),
+            attributes: attributes.into_iter().map(|(name, value)| (name.to_string(), value)).collect()
+        };
+    }
+
+    fn sort_keys(items: Vec<StoredItem>) -> Vec<String> {
+        let mut keys: Vec<String> = items.into_iter().map(|item| item.sort_key).collect();
+        keys.sort();
+        return keys;
+    }
+
+    #[tokio::test]
+    async fn puts_gets_and_deletes_items() {
+        for (name, storage) in backends() {
+            storage.put_item(item("user", "profile", vec![("name", StoredValue::S("Faker".to_string())), ("level", StoredValue::N(500))])).await.unwrap();
+
+            let stored = storage.get_item("user", "profile").await.unwrap().expect(name);
+            assert_eq!(stored.get_s("name"), Some("Faker"), "{}", name);
+            assert_eq!(stored.get_n("level"), Some(500), "{}", name);
+            assert!(storage.get_item("user", "missing").await.unwrap().is_none(), "{}", name);
+
+            storage.put_item(item("user", "profile", vec![("level", StoredValue::N(501))])).await.unwrap();
+            let replaced = storage.get_item("user", "profile").await.unwrap().expect(name);
+            assert_eq!(replaced.get_s("name"), None, "{}", name);
+            assert_eq!(replaced.get_n("level"), Some(501), "{}", name);
+
+            storage.delete_item("user", "profile").await.unwrap();
+            assert!(storage.get_item("user", "profile").await.unwrap().is_none(), "{}", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn queries_by_sort_key_prefix() {
+        for (name, storage) in backends() {
+            for (partition_key, sort_key) in vec![("user", "search#1"), ("user", "search#2"), ("user", "rank#1"), ("other", "search#3")] {
+                storage.put_item(item(partition_key, sort_key, Vec::new())).await.unwrap();
+            }
+
+            assert_eq!(sort_keys(storage.query("user", Some("search#")).await.unwrap()), vec!["search#1", "search#2"], "{}", name);
+            assert_eq!(sort_keys(storage.query("user", None).await.unwrap()), vec!["rank#1", "search#1", "search#2"], "{}", name);
+            assert!(storage.query("nobody", None).await.unwrap().is_empty(), "{}", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn increments_from_zero() {
+        for (name, storage) in backends() {
+            let mut attributes = HashMap::new();
+            attributes.insert("name".to_string(), StoredValue::S("Faker".to_string()));
+
+            storage.increment("user", "searches", "count", attributes.clone()).await.unwrap();
+            assert_eq!(storage.get_item("user", "searches").await.unwrap().expect(name).get_n("count"), Some(1), "{}", name);
+
+            storage.increment("user", "searches", "count", attributes).await.unwrap();
+            let stored = storage.get_item("user", "searches").await.unwrap().expect(name);
+            assert_eq!(stored.get_n("count"), Some(2), "{}", name);
+            assert_eq!(stored.get_s("name"), Some("Faker"), "{}", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn updates_only_when_the_condition_holds() {
+        for (name, storage) in backends() {
+            storage.put_item(item("budgets", "user", vec![("set_at", StoredValue::N(1)), ("limit", StoredValue::N(10))])).await.unwrap();
+
+            let mut attributes = HashMap::new();
+            attributes.insert("checked".to_string(), StoredValue::S("EUW1_1".to_string()));
+
+            assert!(!storage.update_if("budgets", "user", "set_at", StoredValue::N(2), attributes.clone()).await.unwrap(), "{}", name);
+            assert!(!storage.update_if("budgets", "missing", "set_at", StoredValue::N(1), attributes.clone()).await.unwrap(), "{}", name);
+            assert!(storage.get_item("budgets", "missing").await.unwrap().is_none(), "{}", name);
+            assert_eq!(storage.get_item("budgets", "user").await.unwrap().expect(name).get_s("checked"), None, "{}", name);
+
+            assert!(storage.update_if("budgets", "user", "set_at", StoredValue::N(1), attributes).await.unwrap(), "{}", name);
+            let stored = storage.get_item("budgets", "user").await.unwrap().expect(name);
+            assert_eq!(stored.get_s("checked"), Some("EUW1_1"), "{}", name);
+            assert_eq!(stored.get_n("limit"), Some(10), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use async_trait::async_trait;
+use aws_sdk_dynamodb::Client;
+use aws_sdk_dynamodb::model::{AttributeValue};
+use aws_sdk_dynamodb::types::SdkError;
+use std::collections::HashMap;
+use crate::storage::{BotStorage, StorageError, StoredItem, StoredValue};
+
+pub struct DynamoStorage {
+    client: Client,
+    table_name: String
+}
+
+impl DynamoStorage {
+    pub fn new(client: Client, table_name: String) -> DynamoStorage {
+        return DynamoStorage {
+            client: client,
+            table_name: table_name
+        };
+    }
+}
+
+#[async_trait]
+impl BotStorage for DynamoStorage {
+    async fn get_item(&self, partition_key: &str, sort_key: &str) -> Result<Option<StoredItem>, StorageError> {
+        let result = self.client
+            .get_item()
+            .table_name(&self.table_name)
+            .set_key(Some(make_key(partition_key, sort_key)))
+            .send()
+            .await
+            .map_err(to_storage_error)?;
+
+        return Ok(result.item.as_ref().and_then(to_stored_item));
+    }
+
+    async fn put_item(&self, item: StoredItem) -> Result<(), StorageError> {
+        let mut attribute_map = make_key(&item.partition_key, &item.sort_key);
+
+        for (name, value) in item.attributes.into_iter() {
+            attribute_map.insert(name, to_attribute_value(value));
+        }
+
+        self.client
+            .put_item()
+            .table_name(&self.table_name)
+            .set_item(Some(attribute_map))
+            .send()
+            .await
+            .map_err(to_storage_error)?;
+
+        return Ok(());
+    }
+
+    async fn delete_item(&self, partition_key: &str, sort_key: &str) -> Result<(), StorageError> {
+        self.client
+            .delete_item()
+            .table_name(&self.table_name)
+            .set_key(Some(make_key(partition_key, sort_key)))
+            .send()
+            .await
+            .map_err(to_storage_error)?;
+
+        return Ok(());
+    }
+
+    async fn query(&self, partition_key: &str, sort_key_prefix: Option<&str>) -> Result<Vec<StoredItem>, StorageError> {
+        let mut items: Vec<StoredItem> = Vec::new();
+        let mut start_key: Option<HashMap<String, AttributeValue>> = None;
+
+        loop {
+            let mut request = self.client
+                .query()
+                .table_name(&self.table_name)
+                .expression_attribute_names("#partitionKey", "partitionKey")
+                .expression_attribute_values(":partition", AttributeValue::S(partition_key.to_string()))
+                .set_exclusive_start_key(start_key);
+
+            request = match sort_key_prefix {
+                None => request.key_condition_expression("#partitionKey = :partition"),
+                Some(prefix) => request
+                    .key_condition_expression("#partitionKey = :partition AND begins_with(#sortKey, :prefix)")
+                    .expression_attribute_names("#sortKey", "sortKey")
+                    .expression_attribute_values(":prefix", AttributeValue::S(prefix.to_string()))
+            };
+
+            let result = request.send().await.map_err(to_storage_error)?;
+
+            if let Some(results) = result.items {
+                items.extend(results.iter().filter_map(to_stored_item));
+            }
+
+            match result.last_evaluated_key {
+                // No more pages to fetch
+                None => break,
+                Some(key) => start_key = Some(key)
+            }
+        }
+
+        return Ok(items);
+    }
+
+    async fn update_if(
+        &self,
+        partition_key: &str,
+        sort_key: &str,
+        condition_attribute: &str,
+        expected_value: StoredValue,
+        set_attributes: HashMap<String, StoredValue>) -> Result<bool, StorageError> {
+
+        let mut attribute_names: HashMap<String, String> = HashMap::new();
+        attribute_names.insert("#condition".to_string(), condition_attribute.to_string());
+
+        let mut attribute_values: HashMap<String, AttributeValue> = HashMap::new();
+        attribute_values.insert(":expected".to_string(), to_attribute_value(expected_value));
+
+        let mut set_expressions: Vec<String> = Vec::new();
+
+        for (index, (name, value)) in set_attributes.into_iter().enumerate() {
+            attribute_names.insert(format!("#attr{}", index), name);
+            attribute_values.insert(format!(":attr{}", index), to_attribute_value(value));
+            set_expressions.push(format!("#attr{} = :attr{}", index, index));
+        }
+
+        // A missing item has no value for the condition attribute, so the condition also fails for deleted items
+        let result = self.client
+            .update_item()
+            .table_name(&self.table_name)
+            .set_key(Some(make_key(partition_key, sort_key)))
+            .update_expression(format!("SET {}", set_expressions.join(", ")))
+            .condition_expression("#condition = :expected")
+            .set_expression_attribute_names(Some(attribute_names))
+            .set_expression_attribute_values(Some(attribute_values))
+            .send()
+            .await;
+
+        return match result {
+            Ok(_) => Ok(true),
+            Err(SdkError::ServiceError { err, .. }) if err.is_conditional_check_failed_exception() => Ok(false),
+            Err(err) => Err(to_storage_error(err))
+        };
+    }
+
+    async fn increment(
+        &self,
+        partition_key: &str,
+        sort_key: &str,
+        counter_attribute: &str,
+        set_attributes: HashMap<String, StoredValue>) -> Result<(), StorageError> {
+
+        let mut attribute_names: HashMap<String, String> = HashMap::new();
+        attribute_names.insert("#counter".to_string(), counter_attribute.to_string());
+
+        let mut attribute_values: HashMap<String, AttributeValue> = HashMap::new();
+        attribute_values.insert(":initial".to_string(), AttributeValue::N("0".to_string()));
+        attribute_values.insert(":num".to_string(), AttributeValue::N("1".to_string()));
+
+        let mut update_expression = "SET #counter = if_not_exists(#counter, :initial) + :num".to_string();
+
+        for (index, (name, value)) in set_attributes.into_iter().enumerate() {
+            attribute_names.insert(format!("#attr{}", index), name);
+            attribute_values.insert(format!(":attr{}", index), to_attribute_value(value));
+            update_expression.push_str(&format!(", #attr{} = :attr{}", index, index));
+        }
+
+        self.client
+            .update_item()
+            .table_name(&self.table_name)
+            .set_key(Some(make_key(partition_key, sort_key)))
+            .update_expression(update_expression)
+            .set_expression_attribute_names(Some(attribute_names))
+            .set_expression_attribute_values(Some(attribute_values))
+            .send()
+            .await
+            .map_err(to_storage_error)?;
+
+        return Ok(());
+    }
+}
+
+fn make_key(partition_key: &str, sort_key: &str) -> HashMap<String, AttributeValue> {
+    let mut key_map: HashMap<String, AttributeValue> = HashMap::new();
+    key_map.insert("partitionKey".to_string(), AttributeValue::S(partition_key.to_string()));
+    key_map.insert("sortKey".to_string(), AttributeValue::S(sort_key.to_string()));
+
+    return key_map;
+}
+
+fn to_attribute_value(value: StoredValue) -> AttributeValue {
+    match value {
+        StoredValue::S(x) => AttributeValue::S(x),
+        StoredValue::N(x) => AttributeValue::N(x.to_string())
+    }
+}
+
+fn to_stored_item(map: &HashMap<String, AttributeValue>) -> Option<StoredItem> {
+    let partition_key = map.get("partitionKey").and_then(|x| x.as_s().ok())?;
+    let sort_key = map.get("sortKey").and_then(|x| x.as_s().ok())?;
+
+    let attributes = map.iter()
+        .filter(|(name, _)| name.as_str() != "partitionKey" && name.as_str() != "sortKey")
+        .filter_map(|(name, value)| {
+            let stored_value = match value {
+                AttributeValue::S(x) => Some(StoredValue::S(x.to_string())),
+                AttributeValue::N(x) => x.parse::<i64>().ok().map(StoredValue::N),
+                _ => None
+            };
+
+            stored_value.map(|x| (name.to_string(), x))
+        })
+        .collect();
+
+    return Some(StoredItem {
+        partition_key: partition_key.to_string(),
+        sort_key: sort_key.to_string(),
+        attributes: attributes
+    });
+}
+
+fn to_storage_error<E: std::fmt::Debug>(error: E) -> StorageError {
+    return StorageError {
+        description: format!("DynamoDB request failed: {:?}", error)
+    };
+}
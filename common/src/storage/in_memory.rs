@@ -0,0 +1,108 @@
+use async_trait::async_trait;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use crate::storage::{BotStorage, StorageError, StoredItem, StoredValue};
+
+type Attributes = HashMap<String, StoredValue>;
+
+/**
+ * Keeps bot state in process memory. Everything is lost when the process exits, so this is only
+ * really useful for tests and trying the bot out locally.
+ */
+pub struct InMemoryStorage {
+    items: Mutex<BTreeMap<(String, String), Attributes>>
+}
+
+impl InMemoryStorage {
+    pub fn new() -> InMemoryStorage {
+        return InMemoryStorage {
+            items: Mutex::new(BTreeMap::new())
+        };
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, BTreeMap<(String, String), Attributes>>, StorageError> {
+        return self.items.lock().map_err(|_| StorageError {
+            description: "In memory storage lock was poisoned".to_string()
+        });
+    }
+}
+
+#[async_trait]
+impl BotStorage for InMemoryStorage {
+    async fn get_item(&self, partition_key: &str, sort_key: &str) -> Result<Option<StoredItem>, StorageError> {
+        let items = self.lock()?;
+        let key = (partition_key.to_string(), sort_key.to_string());
+
+        return Ok(items.get(&key).map(|attributes| StoredItem {
+            partition_key: partition_key.to_string(),
+            sort_key: sort_key.to_string(),
+            attributes: attributes.clone()
+        }));
+    }
+
+    async fn put_item(&self, item: StoredItem) -> Result<(), StorageError> {
+        let mut items = self.lock()?;
+        items.insert((item.partition_key, item.sort_key), item.attributes);
+
+        return Ok(());
+    }
+
+    async fn delete_item(&self, partition_key: &str, sort_key: &str) -> Result<(), StorageError> {
+        let mut items = self.lock()?;
+        items.remove(&(partition_key.to_string(), sort_key.to_string()));
+
+        return Ok(());
+    }
+
+    async fn query(&self, partition_key: &str, sort_key_prefix: Option<&str>) -> Result<Vec<StoredItem>, StorageError> {
+        let items = self.lock()?;
+        let prefix = sort_key_prefix.unwrap_or("");
+
+        let results = items.iter()
+            .filter(|((partition, sort), _)| partition == partition_key && sort.starts_with(prefix))
+            .map(|((partition, sort), attributes)| StoredItem {
+                partition_key: partition.to_string(),
+                sort_key: sort.to_string(),
+                attributes: attributes.clone()
+            })
+            .collect();
+
+        return Ok(results);
+    }
+
+    async fn update_if(
+        &self,
+        partition_key: &str,
+        sort_key: &str,
+        condition_attribute: &str,
+        expected_value: StoredValue,
+        set_attributes: HashMap<String, StoredValue>) -> Result<bool, StorageError> {
+
+        let mut items = self.lock()?;
+
+        return Ok(match items.get_mut(&(partition_key.to_string(), sort_key.to_string())) {
+            Some(attributes) if attributes.get(condition_attribute) == Some(&expected_value) => {
+                attributes.extend(set_attributes);
+                true
+            },
+            _ => false
+        });
+    }
+
+    async fn increment(
+        &self,
+        partition_key: &str,
+        sort_key: &str,
+        counter_attribute: &str,
+        set_attributes: HashMap<String, StoredValue>) -> Result<(), StorageError> {
+
+        let mut items = self.lock()?;
+        let attributes = items.entry((partition_key.to_string(), sort_key.to_string())).or_insert(HashMap::new());
+
+        let current = attributes.get(counter_attribute).and_then(|x| x.as_n()).unwrap_or(0);
+        attributes.insert(counter_attribute.to_string(), StoredValue::N(current + 1));
+        attributes.extend(set_attributes);
+
+        return Ok(());
+    }
+}
@@ -0,0 +1,199 @@
+use async_trait::async_trait;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use crate::storage::{BotStorage, StorageError, StoredItem, StoredValue};
+
+/**
+ * Stores bot state in a single SQLite table keyed the same way as the DynamoDB table, with the
+ * remaining attributes of each row kept as a JSON object.
+ */
+pub struct SqliteStorage {
+    connection: Mutex<Connection>
+}
+
+impl SqliteStorage {
+    pub fn open(path: &str) -> Result<SqliteStorage, StorageError> {
+        let connection = Connection::open(path).map_err(to_storage_error)?;
+
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS bot_state (
+                partition_key TEXT NOT NULL,
+                sort_key TEXT NOT NULL,
+                attributes TEXT NOT NULL,
+                PRIMARY KEY (partition_key, sort_key)
+            )",
+            []
+        ).map_err(to_storage_error)?;
+
+        return Ok(SqliteStorage {
+            connection: Mutex::new(connection)
+        });
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, Connection>, StorageError> {
+        return self.connection.lock().map_err(|_| StorageError {
+            description: "SQLite connection lock was poisoned".to_string()
+        });
+    }
+}
+
+#[async_trait]
+impl BotStorage for SqliteStorage {
+    async fn get_item(&self, partition_key: &str, sort_key: &str) -> Result<Option<StoredItem>, StorageError> {
+        let connection = self.lock()?;
+
+        let attributes: Option<String> = connection.query_row(
+            "SELECT attributes FROM bot_state WHERE partition_key = ?1 AND sort_key = ?2",
+            params![partition_key, sort_key],
+            |row| row.get(0)
+        ).optional().map_err(to_storage_error)?;
+
+        return match attributes {
+            None => Ok(None),
+            Some(json) => Ok(Some(StoredItem {
+                partition_key: partition_key.to_string(),
+                sort_key: sort_key.to_string(),
+                attributes: parse_attributes(&json)?
+            }))
+        };
+    }
+
+    async fn put_item(&self, item: StoredItem) -> Result<(), StorageError> {
+        let connection = self.lock()?;
+        let attributes = serde_json::to_string(&item.attributes).map_err(to_storage_error)?;
+
+        connection.execute(
+            "INSERT OR REPLACE INTO bot_state (partition_key, sort_key, attributes) VALUES (?1, ?2, ?3)",
+            params![item.partition_key, item.sort_key, attributes]
+        ).map_err(to_storage_error)?;
+
+        return Ok(());
+    }
+
+    async fn delete_item(&self, partition_key: &str, sort_key: &str) -> Result<(), StorageError> {
+        let connection = self.lock()?;
+
+        connection.execute(
+            "DELETE FROM bot_state WHERE partition_key = ?1 AND sort_key = ?2",
+            params![partition_key, sort_key]
+        ).map_err(to_storage_error)?;
+
+        return Ok(());
+    }
+
+    async fn query(&self, partition_key: &str, sort_key_prefix: Option<&str>) -> Result<Vec<StoredItem>, StorageError> {
+        let connection = self.lock()?;
+        let prefix = sort_key_prefix.unwrap_or("");
+
+        let mut statement = connection.prepare(
+            "SELECT sort_key, attributes FROM bot_state WHERE partition_key = ?1 AND substr(sort_key, 1, length(?2)) = ?2 ORDER BY sort_key"
+        ).map_err(to_storage_error)?;
+
+        let rows = statement.query_map(params![partition_key, prefix], |row| {
+            let sort_key: String = row.get(0)?;
+            let attributes: String = row.get(1)?;
+            Ok((sort_key, attributes))
+        }).map_err(to_storage_error)?;
+
+        let mut items: Vec<StoredItem> = Vec::new();
+
+        for row in rows {
+            let (sort_key, attributes) = row.map_err(to_storage_error)?;
+
+            items.push(StoredItem {
+                partition_key: partition_key.to_string(),
+                sort_key: sort_key,
+                attributes: parse_attributes(&attributes)?
+            });
+        }
+
+        return Ok(items);
+    }
+
+    async fn update_if(
+        &self,
+        partition_key: &str,
+        sort_key: &str,
+        condition_attribute: &str,
+        expected_value: StoredValue,
+        set_attributes: HashMap<String, StoredValue>) -> Result<bool, StorageError> {
+
+        let mut connection = self.lock()?;
+        let transaction = connection.transaction().map_err(to_storage_error)?;
+
+        let existing: Option<String> = transaction.query_row(
+            "SELECT attributes FROM bot_state WHERE partition_key = ?1 AND sort_key = ?2",
+            params![partition_key, sort_key],
+            |row| row.get(0)
+        ).optional().map_err(to_storage_error)?;
+
+        let mut attributes = match existing {
+            None => return Ok(false),
+            Some(json) => parse_attributes(&json)?
+        };
+
+        if attributes.get(condition_attribute) != Some(&expected_value) {
+            return Ok(false);
+        }
+
+        attributes.extend(set_attributes);
+        let attributes_json = serde_json::to_string(&attributes).map_err(to_storage_error)?;
+
+        transaction.execute(
+            "UPDATE bot_state SET attributes = ?3 WHERE partition_key = ?1 AND sort_key = ?2",
+            params![partition_key, sort_key, attributes_json]
+        ).map_err(to_storage_error)?;
+
+        transaction.commit().map_err(to_storage_error)?;
+
+        return Ok(true);
+    }
+
+    async fn increment(
+        &self,
+        partition_key: &str,
+        sort_key: &str,
+        counter_attribute: &str,
+        set_attributes: HashMap<String, StoredValue>) -> Result<(), StorageError> {
+
+        let mut connection = self.lock()?;
+        let transaction = connection.transaction().map_err(to_storage_error)?;
+
+        let existing: Option<String> = transaction.query_row(
+            "SELECT attributes FROM bot_state WHERE partition_key = ?1 AND sort_key = ?2",
+            params![partition_key, sort_key],
+            |row| row.get(0)
+        ).optional().map_err(to_storage_error)?;
+
+        let mut attributes = match existing {
+            None => HashMap::new(),
+            Some(json) => parse_attributes(&json)?
+        };
+
+        let current = attributes.get(counter_attribute).and_then(|x| x.as_n()).unwrap_or(0);
+        attributes.insert(counter_attribute.to_string(), StoredValue::N(current + 1));
+        attributes.extend(set_attributes);
+
+        let attributes_json = serde_json::to_string(&attributes).map_err(to_storage_error)?;
+
+        transaction.execute(
+            "INSERT OR REPLACE INTO bot_state (partition_key, sort_key, attributes) VALUES (?1, ?2, ?3)",
+            params![partition_key, sort_key, attributes_json]
+        ).map_err(to_storage_error)?;
+
+        transaction.commit().map_err(to_storage_error)?;
+
+        return Ok(());
+    }
+}
+
+fn parse_attributes(json: &str) -> Result<HashMap<String, StoredValue>, StorageError> {
+    return serde_json::from_str(json).map_err(to_storage_error);
+}
+
+fn to_storage_error<E: std::fmt::Display>(error: E) -> StorageError {
+    return StorageError {
+        description: format!("SQLite storage error: {}", error)
+    };
+}
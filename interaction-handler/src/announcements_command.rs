@@ -0,0 +1,80 @@
+use common::discord_bot_types;
+use common::guild_config::RankAnnouncementThreshold;
+use common::storage::BotStorage;
+use crate::lol_command;
+
+/**
+ * Turns rank change announcements on in the 'channel' option or otherwise the channel the command was used in, or
+ * off if 'enabled' is false. 'threshold' and 'min_lp' choose which changes are announced. Only changes stored
+ * settings, so we can answer straight away.
+ */
+pub async fn configure_announcements(
+    storage: &dyn BotStorage,
+    command: &discord_bot_types::Command,
+    guild_id: &Option<String>,
+    channel_id: &Option<String>) -> String {
+
+    let guild_id = match guild_id {
+        None => return "Rank announcements can only be set up in a server.".to_string(),
+        Some(guild_id) => guild_id
+    };
+
+    let enabled = lol_command::find_boolean_option(command, "enabled").unwrap_or(true);
+    let channel_id = lol_command::find_string_option(command, "channel").or(channel_id.clone());
+
+    let mut config = match common::guild_config::get_guild_config(storage, guild_id).await {
+        Ok(config) => config,
+        Err(err) => {
+            println!("Could not get guild config: {:?}", err);
+            return "Sorry, I couldn't change the announcement settings just now. Please try again in a moment.".to_string();
+        }
+    };
+
+    if let Some(threshold) = lol_command::find_string_option(command, "threshold") {
+        match RankAnnouncementThreshold::from_name(&threshold) {
+            None => return format!("Unrecognised threshold {}", threshold),
+            Some(threshold) => config.rank_announcement_threshold = threshold
+        }
+    }
+
+    // Zero turns LP announcements off
+    if let Some(min_league_points) = lol_command::find_number_option(command, "min_lp") {
+        config.rank_announcement_min_league_points = if min_league_points == 0 {None} else {Some(min_league_points)};
+    }
+
+    let message = match (enabled, channel_id) {
+        (false, _) => {
+            config.rank_announcement_channel_id = None;
+            "Rank changes won't be announced in this server any more.".to_string()
+        },
+        (true, None) => return "Choose a channel to announce rank changes in.".to_string(),
+        (true, Some(channel_id)) => {
+            let message = format!("I'll announce linked members' rank changes in <#{}> {}.", channel_id, create_threshold_string(&config));
+            config.rank_announcement_channel_id = Some(channel_id);
+            message
+        }
+    };
+
+    return match common::guild_config::store_guild_config(storage, &config).await {
+        Ok(()) => message,
+        Err(err) => {
+            println!("Could not store guild config: {:?}", err);
+            "Sorry, I couldn't change the announcement settings just now. Please try again in a moment.".to_string()
+        }
+    };
+}
+
+/**
+ * e.g. 'when they change division or win or lose a promotion series, or gain or lose 100 LP'
+ */
+fn create_threshold_string(config: &common::guild_config::GuildConfig) -> String {
+    let threshold = match config.rank_announcement_threshold {
+        RankAnnouncementThreshold::Tier => "when they change tier".to_string(),
+        RankAnnouncementThreshold::Division => "when they change division or win or lose a promotion series".to_string()
+    };
+
+    return match config.rank_announcement_min_league_points {
+        None => threshold,
+        Some(min_league_points) => format!("{}, or gain or lose {} LP", threshold, min_league_points)
+    };
+}
@@ -0,0 +1,51 @@
+use common::discord_bot_types;
+use common::storage::BotStorage;
+use crate::lol_command;
+
+/**
+ * Opts the server in to the weekly digest, posted to the 'channel' option or otherwise the channel the command was
+ * used in, or opts it out if 'enabled' is false. Only changes stored settings, so we can answer straight away.
+ */
+pub async fn configure_digest(
+    storage: &dyn BotStorage,
+    command: &discord_bot_types::Command,
+    guild_id: &Option<String>,
+    channel_id: &Option<String>) -> String {
+
+    let guild_id = match guild_id {
+        None => return "The weekly digest can only be set up in a server.".to_string(),
+        Some(guild_id) => guild_id
+    };
+
+    let enabled = lol_command::find_boolean_option(command, "enabled").unwrap_or(true);
+    let channel_id = lol_command::find_string_option(command, "channel").or(channel_id.clone());
+
+    let mut config = match common::guild_config::get_guild_config(storage, guild_id).await {
+        Ok(config) => config,
+        Err(err) => {
+            println!("Could not get guild config: {:?}", err);
+            return "Sorry, I couldn't change the digest settings just now. Please try again in a moment.".to_string();
+        }
+    };
+
+    let message = match (enabled, channel_id) {
+        (false, _) => {
+            config.digest_channel_id = None;
+            "The weekly digest is turned off for this server.".to_string()
+        },
+        (true, None) => return "Choose a channel to post the weekly digest in.".to_string(),
+        (true, Some(channel_id)) => {
+            let message = format!("I'll post the weekly digest in <#{}> every Monday.", channel_id);
+            config.digest_channel_id = Some(channel_id);
+            message
+        }
+    };
+
+    return match common::guild_config::store_guild_config(storage, &config).await {
+        Ok(()) => message,
+        Err(err) => {
+            println!("Could not store guild config: {:?}", err);
+            "Sorry, I couldn't change the digest settings just now. Please try again in a moment.".to_string()
+        }
+    };
+}
@@ -0,0 +1,136 @@
+use common::discord_bot_types;
+use common::linked_accounts::{format_member, LinkedAccount};
+use common::player_stats::WeeklyStats;
+use common::storage::BotStorage;
+use crate::lol_command;
+use futures::future::join_all;
+use lol::models::LeagueEntry;
+
+/**
+ * Only this many members are listed, so that the message stays readable in big servers
+ */
+const MAX_LEADERBOARD_ROWS: usize = 20;
+
+const WEEK_SECONDS: u64 = 7 * 86400;
+
+/**
+ * A linked member of the server, with what the scheduled refresh last found out about them
+ */
+struct LeaderboardPlayer {
+    account: LinkedAccount,
+    solo_entry: Option<LeagueEntry>,
+    weekly_stats: Option<WeeklyStats>
+}
+
+/**
+ * Ranks the server's linked members by the 'sort' option: solo queue rank (the default), games played this week,
+ * winrate this week or hours played this week. Everything comes from storage, which the scheduled leaderboard
+ * refresh keeps up to date, so we can answer straight away rather than queueing the command.
+ */
+pub async fn create_leaderboard_message(storage: &dyn BotStorage, command: &discord_bot_types::Command, guild_id: &Option<String>) -> String {
+    let guild_id = match guild_id {
+        None => return "Leaderboards are only available in a server.".to_string(),
+        Some(guild_id) => guild_id
+    };
+
+    let sort = lol_command::find_string_option(command, "sort").unwrap_or("rank".to_string());
+
+    let accounts = match common::linked_accounts::get_linked_accounts(storage, guild_id).await {
+        Ok(accounts) => accounts,
+        Err(err) => {
+            println!("Could not get linked accounts for leaderboard: {:?}", err);
+            return "Sorry, I couldn't get the leaderboard just now. Please try again in a moment.".to_string();
+        }
+    };
+
+    if accounts.is_empty() {
+        return "Nobody has linked their League account in this server yet. Use /link to join the leaderboard.".to_string();
+    }
+
+    let now = common::date_range::now_seconds().unwrap_or(0);
+
+    // Discord only waits 3 seconds for our answer, so every member's data is read at once
+    let players: Vec<LeaderboardPlayer> = join_all(accounts.into_iter().map(|account| get_leaderboard_player(storage, account, now))).await
+        .into_iter()
+        .flatten()
+        .collect();
+
+    let (title, rows) = match sort.as_str() {
+        "games" => ("Most games played this week", create_weekly_rows(&players, |stats| stats.games as f64, |stats| format!("{} games", stats.games))),
+        "winrate" => ("Best winrate this week", create_weekly_rows(&players, weekly_winrate, |stats| {
+            format!("{}% ({}W {}L)", lol::rank::winrate_percentage(stats.wins, stats.games - stats.wins), stats.wins, stats.games - stats.wins)
+        })),
+        "hours" => ("Most hours played this week", create_weekly_rows(&players, |stats| stats.played_for_millis as f64, |stats| {
+            format!("{:.1} hours", stats.played_for_millis as f64 / 3600000.0)
+        })),
+        _ => ("Solo queue leaderboard", create_rank_rows(&players))
+    };
+
+    if rows.is_empty() {
+        return format!("**{}**\nNobody here has any games to rank yet. The leaderboard is refreshed every few hours.", title);
+    }
+
+    let mut message = format!("**{}**\n", title);
+
+    for (position, row) in rows.iter().take(MAX_LEADERBOARD_ROWS).enumerate() {
+        message.push_str(&format!("{}. {}\n", position + 1, row));
+    }
+
+    return message;
+}
+
+async fn get_leaderboard_player(storage: &dyn BotStorage, account: LinkedAccount, now: u64) -> Option<LeaderboardPlayer> {
+    let (latest_snapshot, weekly_stats) = futures::join!(
+        common::rank_history::get_latest_rank_snapshot(storage, &account.puuid, "RANKED_SOLO_5x5"),
+        common::player_stats::get_weekly_stats(storage, &account.puuid)
+    );
+
+    match (latest_snapshot, weekly_stats) {
+        (Ok(latest_snapshot), Ok(weekly_stats)) => Some(LeaderboardPlayer {
+            account: account,
+            solo_entry: latest_snapshot.map(|snapshot| snapshot.entry),
+            // Stats that haven't been refreshed for a week (e.g. if the refresh has stopped) aren't about this week
+            weekly_stats: weekly_stats.filter(|stats| stats.updated_at + WEEK_SECONDS > now)
+        }),
+        (latest_snapshot, weekly_stats) => {
+            println!("Could not get leaderboard data for {}: {:?} {:?}", account.player_name, latest_snapshot.err(), weekly_stats.err());
+            None
+        }
+    }
+}
+
+/**
+ * Ranked players in order of solo queue standing. Unranked players are left off.
+ */
+fn create_rank_rows(players: &Vec<LeaderboardPlayer>) -> Vec<String> {
+    let mut ranked: Vec<(&LeaderboardPlayer, &LeagueEntry)> = players.iter()
+        .filter_map(|player| player.solo_entry.as_ref().map(|entry| (player, entry)))
+        .collect();
+
+    ranked.sort_by(|(_, a), (_, b)| lol::rank::absolute_league_points(b).cmp(&lol::rank::absolute_league_points(a)));
+
+    return ranked.iter().map(|(player, entry)| {
+        format!("{} - {} {} LP", format_member(&player.account.discord_user_id, &player.account.player_name), lol::rank::format_rank(entry), entry.leaguePoints)
+    }).collect();
+}
+
+/**
+ * Players who played this week, ordered by the given stat, highest first
+ */
+fn create_weekly_rows(
+    players: &Vec<LeaderboardPlayer>,
+    sort_value: fn(&WeeklyStats) -> f64,
+    format_value: fn(&WeeklyStats) -> String) -> Vec<String> {
+
+    let mut played: Vec<(&LeaderboardPlayer, &WeeklyStats)> = players.iter()
+        .filter_map(|player| player.weekly_stats.as_ref().filter(|stats| stats.games > 0).map(|stats| (player, stats)))
+        .collect();
+
+    played.sort_by(|(_, a), (_, b)| sort_value(b).partial_cmp(&sort_value(a)).unwrap_or(std::cmp::Ordering::Equal).then(b.games.cmp(&a.games)));
+
+    return played.iter().map(|(player, stats)| format!("{} - {}", format_member(&player.account.discord_user_id, &player.account.player_name), format_value(stats))).collect();
+}
+
+fn weekly_winrate(stats: &WeeklyStats) -> f64 {
+    return stats.wins as f64 / stats.games as f64;
+}
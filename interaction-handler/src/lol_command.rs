@@ -1,36 +1,300 @@
 use crate::discord_bot_types;
 
+/**
+ * Turns a slash command invocation into the command we queue for the async responder
+ */
+pub fn build_queued_command(
+    command: discord_bot_types::Command,
+    discord_user_id: String,
+    guild_id: Option<String>,
+    channel_id: Option<String>,
+    token: String,
+    application_id: String) -> Result<discord_bot_types::QueuedCommand, discord_bot_types::BotError> {
+
+    match command.name.as_str() {
+        "played" | "ranked" => build_played_command(command, discord_user_id, guild_id, token, application_id).map(discord_bot_types::QueuedCommand::Played),
+        "rank" => build_rank_command(command, discord_user_id, token, application_id).map(discord_bot_types::QueuedCommand::Rank),
+        "live" => build_live_command(command, discord_user_id, token, application_id).map(discord_bot_types::QueuedCommand::Live),
+        "mastery" => build_mastery_command(command, discord_user_id, token, application_id).map(discord_bot_types::QueuedCommand::Mastery),
+        "compare" => build_compare_command(command, discord_user_id, token, application_id).map(discord_bot_types::QueuedCommand::Compare),
+        "link" => build_link_command(command, discord_user_id, guild_id, token, application_id).map(discord_bot_types::QueuedCommand::Link),
+        "track" => build_track_command(command, discord_user_id, guild_id, channel_id, token, application_id).map(discord_bot_types::QueuedCommand::Track),
+        "budget" => build_budget_command(command, discord_user_id, guild_id, channel_id, token, application_id).map(discord_bot_types::QueuedCommand::Budget),
+        name => Err(discord_bot_types::BotError {
+            statusCode: 400,
+            body: format!("Unrecognised command {}", name)
+        })
+    }
+}
+
 pub fn build_played_command(
     command: discord_bot_types::Command,
     discord_user_id: String,
+    guild_id: Option<String>,
     token: String,
     application_id: String) -> Result<discord_bot_types::PlayedCommand, discord_bot_types::BotError> {
-    let player_name = command.options.iter().find_map(|x| match x {
-        discord_bot_types::CommandOption::NumberCommandOption(x) => None,
-        discord_bot_types::CommandOption::StringCommandOption(option) => {
-            if option.name == "user" {Some(&option.value)} else {None}
+    let player_name = find_string_option(&command, "user").ok_or(discord_bot_types::BotError {
+        statusCode: 500,
+        body: "Could not find player name".to_string()
+    })?;
+
+    let days_requested = find_number_option(&command, "days");
+    let range = find_date_range(&command)?;
+
+    let private = find_boolean_option(&command, "private").unwrap_or(false);
+
+    let game_type = if command.name == "ranked" {Some("ranked".to_string())} else {None};
+
+    return Ok(discord_bot_types::PlayedCommand {
+        player_name: player_name,
+        days: days_requested.unwrap_or(7),
+        range: Some(range),
+        discord_user_id: discord_user_id,
+        game_type: game_type,
+        guild_id: guild_id,
+        detail: match find_string_option(&command, "detail").as_deref() {
+            Some("stats") => discord_bot_types::DetailLevel::Stats,
+            Some("full") => discord_bot_types::DetailLevel::Full,
+            _ => discord_bot_types::DetailLevel::Basic
         },
-    }).ok_or(discord_bot_types::BotError {
+        queue: find_number_option(&command, "queue"),
+        champion: find_string_option(&command, "champion"),
+        role: find_string_option(&command, "role"),
+        token: token,
+        application_id: application_id,
+        private: private
+    });
+}
+
+pub fn build_rank_command(
+    command: discord_bot_types::Command,
+    discord_user_id: String,
+    token: String,
+    application_id: String) -> Result<discord_bot_types::RankCommand, discord_bot_types::BotError> {
+    let player_name = find_string_option(&command, "user").ok_or(discord_bot_types::BotError {
         statusCode: 500,
         body: "Could not find player name".to_string()
     })?;
 
-    let days_requested = command.options.iter().find_map(|x| match x {
-        discord_bot_types::CommandOption::NumberCommandOption(option) => {if option.name == "days" {Some(option.value)} else {None}},
-        discord_bot_types::CommandOption::StringCommandOption(option) => None,
-    }).ok_or(discord_bot_types::BotError {
+    return Ok(discord_bot_types::RankCommand {
+        player_name: player_name,
+        discord_user_id: discord_user_id,
+        token: token,
+        application_id: application_id,
+        private: find_boolean_option(&command, "private").unwrap_or(false)
+    });
+}
+
+pub fn build_live_command(
+    command: discord_bot_types::Command,
+    discord_user_id: String,
+    token: String,
+    application_id: String) -> Result<discord_bot_types::LiveCommand, discord_bot_types::BotError> {
+    let player_name = find_string_option(&command, "user").ok_or(discord_bot_types::BotError {
         statusCode: 500,
         body: "Could not find player name".to_string()
     })?;
 
-    let game_type = if command.name == "ranked" {Some("ranked".to_string())} else {None};
+    return Ok(discord_bot_types::LiveCommand {
+        player_name: player_name,
+        discord_user_id: discord_user_id,
+        token: token,
+        application_id: application_id,
+        private: find_boolean_option(&command, "private").unwrap_or(false)
+    });
+}
 
-    return Ok(discord_bot_types::PlayedCommand {
-        player_name: player_name.to_string(),
-        days: days_requested,
+pub fn build_mastery_command(
+    command: discord_bot_types::Command,
+    discord_user_id: String,
+    token: String,
+    application_id: String) -> Result<discord_bot_types::MasteryCommand, discord_bot_types::BotError> {
+    let player_name = find_string_option(&command, "user").ok_or(discord_bot_types::BotError {
+        statusCode: 500,
+        body: "Could not find player name".to_string()
+    })?;
+
+    return Ok(discord_bot_types::MasteryCommand {
+        player_name: player_name,
         discord_user_id: discord_user_id,
-        game_type: game_type,
         token: token,
-        application_id: application_id
+        application_id: application_id,
+        count: find_number_option(&command, "count"),
+        champion: find_string_option(&command, "champion"),
+        private: find_boolean_option(&command, "private").unwrap_or(false)
+    });
+}
+
+pub fn build_compare_command(
+    command: discord_bot_types::Command,
+    discord_user_id: String,
+    token: String,
+    application_id: String) -> Result<discord_bot_types::CompareCommand, discord_bot_types::BotError> {
+    let player_name = find_string_option(&command, "user").ok_or(discord_bot_types::BotError {
+        statusCode: 500,
+        body: "Could not find player name".to_string()
+    })?;
+
+    let other_player_name = find_string_option(&command, "other_user").ok_or(discord_bot_types::BotError {
+        statusCode: 500,
+        body: "Could not find other player name".to_string()
+    })?;
+
+    return Ok(discord_bot_types::CompareCommand {
+        range: find_date_range(&command)?,
+        player_name: player_name,
+        other_player_name: other_player_name,
+        discord_user_id: discord_user_id,
+        token: token,
+        application_id: application_id,
+        private: find_boolean_option(&command, "private").unwrap_or(false)
+    });
+}
+
+pub fn build_link_command(
+    command: discord_bot_types::Command,
+    discord_user_id: String,
+    guild_id: Option<String>,
+    token: String,
+    application_id: String) -> Result<discord_bot_types::LinkCommand, discord_bot_types::BotError> {
+    let player_name = find_string_option(&command, "user").ok_or(discord_bot_types::BotError {
+        statusCode: 500,
+        body: "Could not find player name".to_string()
+    })?;
+
+    let guild_id = guild_id.ok_or(discord_bot_types::BotError {
+        statusCode: 400,
+        body: "Accounts can only be linked in a server.".to_string()
+    })?;
+
+    // Nobody else needs to see who linked what
+    return Ok(discord_bot_types::LinkCommand {
+        player_name: player_name,
+        guild_id: guild_id,
+        discord_user_id: discord_user_id,
+        token: token,
+        application_id: application_id,
+        private: true
+    });
+}
+
+pub fn build_track_command(
+    command: discord_bot_types::Command,
+    discord_user_id: String,
+    guild_id: Option<String>,
+    channel_id: Option<String>,
+    token: String,
+    application_id: String) -> Result<discord_bot_types::TrackCommand, discord_bot_types::BotError> {
+    let player_name = find_string_option(&command, "user").ok_or(discord_bot_types::BotError {
+        statusCode: 500,
+        body: "Could not find player name".to_string()
+    })?;
+
+    let guild_id = guild_id.ok_or(discord_bot_types::BotError {
+        statusCode: 400,
+        body: "Players can only be tracked in a server.".to_string()
+    })?;
+
+    // Posts go to the channel the command was used in unless another was chosen
+    let channel_id = find_string_option(&command, "channel").or(channel_id).ok_or(discord_bot_types::BotError {
+        statusCode: 400,
+        body: "Choose a channel to post the player's games in.".to_string()
+    })?;
+
+    return Ok(discord_bot_types::TrackCommand {
+        player_name: player_name,
+        guild_id: guild_id,
+        channel_id: channel_id,
+        stop: find_boolean_option(&command, "stop").unwrap_or(false),
+        discord_user_id: discord_user_id,
+        token: token,
+        application_id: application_id,
+        private: find_boolean_option(&command, "private").unwrap_or(false)
+    });
+}
+
+pub fn build_budget_command(
+    command: discord_bot_types::Command,
+    discord_user_id: String,
+    guild_id: Option<String>,
+    channel_id: Option<String>,
+    token: String,
+    application_id: String) -> Result<discord_bot_types::BudgetCommand, discord_bot_types::BotError> {
+
+    let action = match find_string_option(&command, "action").as_deref() {
+        None | Some("view") => discord_bot_types::BudgetAction::View,
+        Some("set") => discord_bot_types::BudgetAction::Set,
+        Some("clear") => discord_bot_types::BudgetAction::Clear,
+        Some(other) => return Err(discord_bot_types::BotError {
+            statusCode: 400,
+            body: format!("Unrecognised action {}", other)
+        })
+    };
+
+    let hours = find_number_option(&command, "hours");
+
+    if action == discord_bot_types::BudgetAction::Set && hours.is_none() {
+        return Err(discord_bot_types::BotError {
+            statusCode: 400,
+            body: "Choose how many hours you want to play for.".to_string()
+        });
+    }
+
+    let period = find_string_option(&command, "period")
+        .and_then(|period| common::playtime_budgets::BudgetPeriod::from_name(&period))
+        .unwrap_or(common::playtime_budgets::BudgetPeriod::Daily);
+
+    // Budgets are the user's own business, so only they see the response. Going over budget is announced publicly.
+    return Ok(discord_bot_types::BudgetCommand {
+        action: action,
+        player_name: find_string_option(&command, "user").unwrap_or("".to_string()),
+        guild_id: guild_id,
+        channel_id: channel_id,
+        hours: hours,
+        period: period,
+        dm: find_boolean_option(&command, "dm").unwrap_or(false),
+        discord_user_id: discord_user_id,
+        token: token,
+        application_id: application_id,
+        private: true
+    });
+}
+
+/**
+ * The period the command's days, range, since and until options describe
+ */
+fn find_date_range(command: &discord_bot_types::Command) -> Result<common::date_range::DateRange, discord_bot_types::BotError> {
+    let now = common::date_range::now_seconds().unwrap_or(0);
+
+    return common::date_range::resolve_date_range(
+        find_string_option(command, "range").as_deref(),
+        find_string_option(command, "since").as_deref(),
+        find_string_option(command, "until").as_deref(),
+        find_number_option(command, "days"),
+        now
+    ).map_err(|message| discord_bot_types::BotError {
+        statusCode: 400,
+        body: message
+    });
+}
+
+pub fn find_string_option(command: &discord_bot_types::Command, name: &str) -> Option<String> {
+    return command.options.iter().find_map(|x| match x {
+        discord_bot_types::CommandOption::StringCommandOption(option) if option.name == name => Some(option.value.to_string()),
+        _ => None
+    });
+}
+
+pub fn find_number_option(command: &discord_bot_types::Command, name: &str) -> Option<u64> {
+    return command.options.iter().find_map(|x| match x {
+        discord_bot_types::CommandOption::NumberCommandOption(option) if option.name == name => Some(option.value),
+        _ => None
+    });
+}
+
+pub fn find_boolean_option(command: &discord_bot_types::Command, name: &str) -> Option<bool> {
+    return command.options.iter().find_map(|x| match x {
+        discord_bot_types::CommandOption::BooleanCommandOption(option) if option.name == name => Some(option.value),
+        _ => None
     });
 }
@@ -2,30 +2,42 @@ use common::discord_bot_types;
 use lambda_runtime::{service_fn, LambdaEvent, Error};
 use serde_json::{Value, json};
 use aws_sdk_sqs::Client;
-use aws_sdk_dynamodb;
+use aws_sdk_sqs::types::SdkError;
+use aws_sdk_sqs::error::SendMessageError;
 use aws_config::meta::region::RegionProviderChain;
 use std::env;
-use chrono;
 use common;
+use common::storage::BotStorage;
 use std::cmp::Ordering::{Less, Equal, Greater};
+use std::time::Duration;
+use tokio::time::sleep;
 
+mod announcements_command;
 mod auth;
+mod digest_command;
+mod leaderboard_command;
 mod lol_command;
 
+/**
+ * Discord only waits 3 seconds for our response, so we only retry sending to the queue a few times
+ */
+const SQS_SEND_ATTEMPTS: u32 = 4;
+const SQS_RETRY_BASE_DELAY_MILLIS: u64 = 100;
+
 #[tokio::main]
 async fn main() -> Result<(), Error> {
     let region_provider = RegionProviderChain::default_provider().or_else("us-east-1");
     let config = aws_config::from_env().region(region_provider).load().await;
     let client = Client::new(&config);
-    let dynamo_client = aws_sdk_dynamodb::Client::new(&config);
+    let storage = common::storage::create_storage(common::storage::storage_config_from_env()?).await?;
 
-    let func = service_fn(|x| func(&client, &dynamo_client, x));
+    let func = service_fn(|x| func(&client, storage.as_ref(), x));
     lambda_runtime::run(func).await?;
     Ok(())
 }
 
-async fn func(sqs_client: &Client, dynamo_client: &aws_sdk_dynamodb::Client, event: LambdaEvent<Value>) -> Result<Value, serde_json::Error> {
-    let result = process_request(sqs_client, dynamo_client, event).await;
+async fn func(sqs_client: &Client, storage: &dyn BotStorage, event: LambdaEvent<Value>) -> Result<Value, serde_json::Error> {
+    let result = process_request(sqs_client, storage, event).await;
 
     // AWS Lambda expects the returned 'body' field to be a JSON string, so we convert the bot response to a JSON string
     // and return it with the response headers and HTTP status code
@@ -50,7 +62,7 @@ async fn func(sqs_client: &Client, dynamo_client: &aws_sdk_dynamodb::Client, eve
     return send;
 }
 
-async fn process_request(sqs_client: &Client, dynamo_client: &aws_sdk_dynamodb::Client, event: LambdaEvent<Value>) -> Result<discord_bot_types::BotResponse, discord_bot_types::BotError> {
+async fn process_request(sqs_client: &Client, storage: &dyn BotStorage, event: LambdaEvent<Value>) -> Result<discord_bot_types::BotResponse, discord_bot_types::BotError> {
     let (event, _context) = event.into_parts();
     auth::verify_request(&event).then(|| true).ok_or(discord_bot_types::BotError{statusCode: 401, body: "invalid request signature".to_string()})?;
 
@@ -69,15 +81,48 @@ async fn process_request(sqs_client: &Client, dynamo_client: &aws_sdk_dynamodb::
             let command = payload_value.data.ok_or(make_validation_error_response("Command missing 'data' field.".to_string()))?;
             let member = payload_value.member.ok_or(make_validation_error_response("Command missing 'member' field.".to_string()))?;
 
-            let played_command = lol_command::build_played_command(command, member.user.id, payload_value.token, payload_value.application_id);
+            // Unlinking only needs storage, so there's no need to queue it for the responder
+            if command.name == "unlink" {
+                return Ok(unlink_account(storage, &payload_value.guild_id, &member.user.id).await);
+            }
+
+            if command.name == "announcements" {
+                let message = announcements_command::configure_announcements(storage, &command, &payload_value.guild_id, &payload_value.channel_id).await;
+                return Ok(make_ephemeral_message_response(&message));
+            }
+
+            if command.name == "digest" {
+                let message = digest_command::configure_digest(storage, &command, &payload_value.guild_id, &payload_value.channel_id).await;
+                return Ok(make_ephemeral_message_response(&message));
+            }
+
+            // The leaderboard is built from stored data, so we can answer straight away
+            if command.name == "leaderboard" {
+                let message = leaderboard_command::create_leaderboard_message(storage, &command, &payload_value.guild_id).await;
+                return Ok(make_message_response(&message));
+            }
+
+            let queued_command = lol_command::build_queued_command(command, member.user.id, payload_value.guild_id, payload_value.channel_id, payload_value.token, payload_value.application_id);
 
-            match played_command {
+            match queued_command {
+                // The user's options didn't make sense, so tell them why
+                Err(x) if x.statusCode == 400 => {
+                    return Ok(make_ephemeral_message_response(&x.body))
+                },
                 Err(x) => {
                     return Err(make_error_response(400, "Could not parse options"))
                 },
-                Ok (options) => {
-                    write_command_to_queue(sqs_client, options).await?;
-                    return create_deferred_command_response()
+                Ok (command) => {
+                    let private = command.is_private();
+                    let queue_result = write_command_to_queue(sqs_client, command, &payload_value.id).await;
+
+                    return match queue_result {
+                        Ok(()) => create_deferred_command_response(private),
+                        Err(err) => {
+                            println!("Could not queue command: {:?}", err);
+                            Ok(make_ephemeral_message_response("Sorry, I couldn't start working on that just now. Please try again in a moment."))
+                        }
+                    }
                 }
             }
 
@@ -88,11 +133,15 @@ async fn process_request(sqs_client: &Client, dynamo_client: &aws_sdk_dynamodb::
             let options = command.options;
             let discord_user_id = payload_value.member.map(|member| member.user.id);
 
-            let suggestions = match discord_user_id {
-                None => Vec::new(),
-                Some(user_id) => {
-                        generate_username_autocomplete_suggestions(dynamo_client, &user_id, options).await
-                }
+            let focused_option = options.iter().find_map(|x| match x {
+                discord_bot_types::CommandOption::StringCommandOption(y) if y.focused == Some(true) => Some(y.name.to_string()),
+                _ => None
+            });
+
+            let suggestions = match (focused_option.as_deref(), discord_user_id) {
+                (Some("champion"), _) => generate_champion_autocomplete_suggestions(options).await,
+                (Some("user") | Some("other_user"), Some(user_id)) => generate_username_autocomplete_suggestions(storage, &user_id, options).await,
+                _ => Vec::new()
             };
 
             println!("Suggestions: {:?}", suggestions);
@@ -108,7 +157,8 @@ async fn process_request(sqs_client: &Client, dynamo_client: &aws_sdk_dynamodb::
                         discord_bot_types::Data {
                             tts: None,
                             content: None,
-                            choices: Some(suggestions)
+                            choices: Some(suggestions),
+                            ..Default::default()
                         }
                     )
                 }
@@ -120,19 +170,34 @@ async fn process_request(sqs_client: &Client, dynamo_client: &aws_sdk_dynamodb::
     };
 }
 
+async fn unlink_account(storage: &dyn BotStorage, guild_id: &Option<String>, discord_user_id: &str) -> discord_bot_types::BotResponse {
+    let guild_id = match guild_id {
+        None => return make_ephemeral_message_response("Accounts can only be linked in a server."),
+        Some(guild_id) => guild_id
+    };
+
+    return match common::linked_accounts::unlink_account(storage, guild_id, discord_user_id).await {
+        Ok(()) => make_ephemeral_message_response("Your League account is no longer linked in this server."),
+        Err(err) => {
+            println!("Could not unlink account: {:?}", err);
+            make_ephemeral_message_response("Sorry, I couldn't unlink your account just now. Please try again in a moment.")
+        }
+    };
+}
+
 async fn generate_username_autocomplete_suggestions(
-    dynamo_client: &aws_sdk_dynamodb::Client,
+    storage: &dyn BotStorage,
     discord_user_id: &str,
     input: Vec<discord_bot_types::CommandOption>) -> Vec<discord_bot_types::StringChoice> {
     let name_field = input.into_iter().find_map(|x| match x {
-        discord_bot_types::CommandOption::StringCommandOption(y) if y.name == "user" && y.focused == Some(true) => Some(y.value),
+        discord_bot_types::CommandOption::StringCommandOption(y) if (y.name == "user" || y.name == "other_user") && y.focused == Some(true) => Some(y.value),
         _ => None
     });
 
     match name_field {
         None => return Vec::new(),
         Some(name_prefix) => {
-            let searches = common::search_history::get_searches(dynamo_client, discord_user_id).await;
+            let searches = common::search_history::get_searches(storage, discord_user_id).await;
 
             match searches {
                 Err(_) => {
@@ -162,40 +227,188 @@ async fn generate_username_autocomplete_suggestions(
     };
 }
 
-async fn write_command_to_queue(sqs_client: &Client, played_command: common::discord_bot_types::PlayedCommand) -> Result<(), discord_bot_types::BotError> {
+async fn generate_champion_autocomplete_suggestions(input: Vec<discord_bot_types::CommandOption>) -> Vec<discord_bot_types::StringChoice> {
+    let champion_field = input.into_iter().find_map(|x| match x {
+        discord_bot_types::CommandOption::StringCommandOption(y) if y.name == "champion" && y.focused == Some(true) => Some(y.value),
+        _ => None
+    });
+
+    let typed = match champion_field {
+        None => return Vec::new(),
+        Some(typed) => lol::static_data::normalise_name(&typed)
+    };
+
+    let champions = match lol::static_data::get_champions().await {
+        Ok(champions) => champions,
+        Err(err) => {
+            println!("Could not get champions for autocomplete: {:?}", err);
+            return Vec::new();
+        }
+    };
+
+    let mut matches = champions.iter()
+        .filter_map(|champion| champion_match_score(&typed, &champion.name).map(|score| (score, champion.name.to_string())))
+        .collect::<Vec<(u64, String)>>();
+
+    matches.sort();
+
+    return matches
+        .into_iter()
+        .map(|(_, name)| {
+            discord_bot_types::StringChoice {
+                name: name.clone(),
+                value: name
+            }
+        })
+        .take(25)
+        .collect();
+}
+
+/**
+ * How well what the user has typed so far matches the champion's name, lower being better, or None if it doesn't
+ * match at all. Names starting with the input come first, then names with a word starting with it (e.g. 'fate' for
+ * Twisted Fate), then names containing it, then names containing its letters in order (e.g. 'mf' for Miss Fortune)
+ * and lastly names that are a typo or two away from it.
+ */
+fn champion_match_score(typed: &str, champion_name: &str) -> Option<u64> {
+    let name = lol::static_data::normalise_name(champion_name);
+
+    if name.starts_with(typed) {
+        return Some(0);
+    }
+
+    if champion_name.split(|character: char| !character.is_alphanumeric()).any(|word| lol::static_data::normalise_name(word).starts_with(typed)) {
+        return Some(1);
+    }
+
+    if name.contains(typed) {
+        return Some(2);
+    }
+
+    let mut name_characters = name.chars();
+    if typed.chars().all(|character| name_characters.any(|name_character| name_character == character)) {
+        return Some(3);
+    }
+
+    // Compare against the same number of letters from the start of the name, since the user may not have finished typing
+    let typed_length = typed.chars().count();
+    let name_prefix: String = name.chars().take(typed_length).collect();
+    let allowed_typos = if typed_length < 4 {0} else if typed_length < 7 {1} else {2};
+    let distance = edit_distance(typed, &name_prefix);
+
+    if distance <= allowed_typos {
+        return Some(4 + distance);
+    }
+
+    return None;
+}
+
+/**
+ * The number of single character insertions, deletions or substitutions needed to turn one string into the other
+ */
+fn edit_distance(a: &str, b: &str) -> u64 {
+    let b_characters: Vec<char> = b.chars().collect();
+    let mut previous_row: Vec<u64> = (0..=b_characters.len() as u64).collect();
+
+    for (i, a_character) in a.chars().enumerate() {
+        let mut row: Vec<u64> = vec![i as u64 + 1];
+
+        for (j, b_character) in b_characters.iter().enumerate() {
+            let substitution_cost = if a_character == *b_character {0} else {1};
+            let cost = (previous_row[j] + substitution_cost)
+                .min(previous_row[j + 1] + 1)
+                .min(row[j] + 1);
+
+            row.push(cost);
+        }
+
+        previous_row = row;
+    }
+
+    return previous_row[b_characters.len()];
+}
+
+async fn write_command_to_queue(sqs_client: &Client, command: discord_bot_types::QueuedCommand, interaction_id: &str) -> Result<(), discord_bot_types::BotError> {
 
     let queue_url = env::var("MATCHES_QUEUE_URL").map_err(|x| discord_bot_types::BotError {
         statusCode: 500,
         body: "Missing MATCHES_QUEUE_URL environment variable".to_string()
     })?;
 
-    let msg_body = serde_json::to_string(&played_command).map_err(|X| discord_bot_types::BotError {
+    let msg_body = serde_json::to_string(&command).map_err(|X| discord_bot_types::BotError {
         statusCode: 500,
         body: "Could not write SQS payload to JSON string".to_string()
     })?;
 
     println!("Queue URL is {}", queue_url);
 
-    let now = chrono::offset::Utc::now();
-
-    let mut dedup_id = format!("{}-{}-{}", played_command.player_name, played_command.days, now.to_rfc3339());
-    dedup_id.retain(|c| !c.is_whitespace());
-
-    let send_result = sqs_client
-        .send_message()
-        .queue_url(queue_url)
-        .message_body(msg_body)
-        .message_group_id("LolCommandGroup")
-        .message_deduplication_id(dedup_id)
-        .send()
-        .await;
-
-    println!("SQS send result: {:?}", send_result);
+    // Discord gives every interaction a unique ID, so it's a natural deduplication ID
+    let dedup_id = interaction_id.to_string();
+
+    let mut attempt: u32 = 1;
+
+    loop {
+        // The deduplication ID stays the same across attempts so that an attempt which actually reached
+        // the queue before failing isn't processed twice
+        let send_result = sqs_client
+            .send_message()
+            .queue_url(&queue_url)
+            .message_body(&msg_body)
+            .message_group_id("LolCommandGroup")
+            .message_deduplication_id(&dedup_id)
+            .send()
+            .await;
+
+        println!("SQS send result (attempt {}): {:?}", attempt, send_result);
+
+        match send_result {
+            Ok(_) => return Ok(()),
+            Err(err) if attempt < SQS_SEND_ATTEMPTS && is_transient_sqs_error(&err) => {
+                let backoff = Duration::from_millis(SQS_RETRY_BASE_DELAY_MILLIS * 2u64.pow(attempt));
+                println!("Transient SQS error, retrying in {:?}", backoff);
+                sleep(backoff).await;
+                attempt = attempt + 1;
+            },
+            Err(err) => {
+                return Err(discord_bot_types::BotError {
+                    statusCode: 503,
+                    body: format!("Could not send command to queue: {}", err)
+                });
+            }
+        }
+    }
+}
 
-    Ok(())
+/**
+ * Whether the SQS error is worth retrying (timeouts, connection problems, throttling and server side errors)
+ */
+fn is_transient_sqs_error(error: &SdkError<SendMessageError>) -> bool {
+    match error {
+        SdkError::TimeoutError(_) => true,
+        SdkError::DispatchFailure(_) => true,
+        SdkError::ResponseError { .. } => true,
+        SdkError::ServiceError { raw, .. } => {
+            let status = raw.http().status();
+            status.is_server_error() || status.as_u16() == 429
+        },
+        SdkError::ConstructionFailure(_) => false
+    }
 }
 
-fn create_deferred_command_response() -> Result<discord_bot_types::BotResponse, discord_bot_types::BotError> {
+/**
+ * Tells Discord we'll respond later. If the response is private, the message we later edit in is only shown to the user
+ * who invoked the command.
+ */
+fn create_deferred_command_response(private: bool) -> Result<discord_bot_types::BotResponse, discord_bot_types::BotError> {
+
+    let data = if private {
+        Some(discord_bot_types::Data {
+            flags: Some(discord_bot_types::EPHEMERAL_FLAG),
+            ..Default::default()
+        })
+    } else {
+        None
+    };
 
     return Ok(discord_bot_types::BotResponse {
             headers: discord_bot_types::Headers {
@@ -204,11 +417,54 @@ fn create_deferred_command_response() -> Result<discord_bot_types::BotResponse,
             statusCode: 200,
             body: discord_bot_types::Body {
                 typeField: 5,
-                data: None
+                data: data
             }
     });
 }
 
+/**
+ * A message shown to everyone in the channel. Mentions in it don't notify anyone.
+ */
+fn make_message_response(message: &str) -> discord_bot_types::BotResponse {
+    return discord_bot_types::BotResponse {
+        headers: discord_bot_types::Headers {
+            contentType: "application/json".to_string()
+        },
+        statusCode: 200,
+        body: discord_bot_types::Body {
+            typeField: 4,
+            data: Some(
+                discord_bot_types::Data {
+                    tts: None,
+                    content: Some(message.to_string()),
+                    allowed_mentions: Some(discord_bot_types::AllowedMentions::default()),
+                    ..Default::default()
+                }
+            )
+        }
+    }
+}
+
+fn make_ephemeral_message_response(message: &str) -> discord_bot_types::BotResponse {
+    return discord_bot_types::BotResponse {
+        headers: discord_bot_types::Headers {
+            contentType: "application/json".to_string()
+        },
+        statusCode: 200,
+        body: discord_bot_types::Body {
+            typeField: 4,
+            data: Some(
+                discord_bot_types::Data {
+                    tts: None,
+                    content: Some(message.to_string()),
+                    flags: Some(discord_bot_types::EPHEMERAL_FLAG),
+                    ..Default::default()
+                }
+            )
+        }
+    }
+}
+
 fn make_validation_error_response(error: String) -> discord_bot_types::BotError {
     return discord_bot_types::BotError {
         statusCode: 400,
@@ -235,3 +491,63 @@ fn make_ping_response() -> discord_bot_types::BotResponse {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(typed: &str, champion_name: &str) -> Option<u64> {
+        return champion_match_score(&lol::static_data::normalise_name(typed), champion_name);
+    }
+
+    #[test]
+    fn matches_the_start_of_the_name_first() {
+        assert_eq!(score("ah", "Ahri"), Some(0));
+        assert_eq!(score("KAI'S", "Kai'Sa"), Some(0));
+    }
+
+    #[test]
+    fn matches_the_start_of_a_later_word() {
+        assert_eq!(score("fate", "Twisted Fate"), Some(1));
+    }
+
+    #[test]
+    fn matches_names_containing_the_input() {
+        assert_eq!(score("isa", "Kai'Sa"), Some(2));
+    }
+
+    #[test]
+    fn matches_letters_in_order() {
+        assert_eq!(score("mf", "Miss Fortune"), Some(3));
+        assert_eq!(score("fm", "Miss Fortune"), None);
+    }
+
+    #[test]
+    fn allows_more_typos_the_more_is_typed() {
+        assert_eq!(score("ahro", "Ahri"), Some(5));
+        assert_eq!(score("axr", "Ahri"), None);
+        assert_eq!(score("jinxxed", "Jinx"), None);
+        assert_eq!(score("catalyn", "Caitlyn"), Some(6));
+    }
+
+    #[test]
+    fn ranks_better_matches_first() {
+        let mut matches: Vec<(u64, &str)> = vec!["Kha'Zix", "Lee Sin", "Akali", "Karma"]
+            .into_iter()
+            .filter_map(|name| score("ka", name).map(|score| (score, name)))
+            .collect();
+        matches.sort();
+
+        assert_eq!(matches, vec![(0, "Karma"), (2, "Akali"), (3, "Kha'Zix")]);
+    }
+
+    #[test]
+    fn counts_edits_between_strings() {
+        assert_eq!(edit_distance("ahri", "ahri"), 0);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("abc", ""), 3);
+        assert_eq!(edit_distance("ahri", "ahro"), 1);
+        assert_eq!(edit_distance("ahri", "ahir"), 2);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+}
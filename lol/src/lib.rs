@@ -1,12 +1,21 @@
 use std::collections::HashMap;
 use reqwest;
 use serde_json::{Value};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use futures::future::join_all;
+use futures::stream::{FuturesUnordered, Stream};
 use std::env;
 
 pub mod api_fetcher;
 pub mod models;
+pub mod rank;
+pub mod static_data;
+pub mod stats;
+pub mod streaks;
+
+/**
+ * The match API can't list games from before 16 June 2021 (in seconds since the epoch)
+ */
+pub const MATCH_HISTORY_START: u64 = 1623801600;
 
 pub async fn get_puuid(client: &api_fetcher::BoundedHttpFetcher, region: &str, user_name: &str, api_key: &str) -> Result<String, models::LolApiError> {
     let request_url = format!("https://{}.api.riotgames.com/lol/summoner/v4/summoners/by-name/{}?api_key={}", region, user_name, api_key);
@@ -40,6 +49,38 @@ pub async fn get_encrypted_summoner_id(client: &api_fetcher::BoundedHttpFetcher,
     }
 }
 
+pub async fn get_summoner(client: &api_fetcher::BoundedHttpFetcher, region: &str, user_name: &str, api_key: &str) -> Result<models::Summoner, models::LolApiError> {
+    let request_url = format!("https://{}.api.riotgames.com/lol/summoner/v4/summoners/by-name/{}?api_key={}", region, user_name, api_key);
+    let res = api_fetcher::get_request(client, request_url).await?;
+    let status_code = res.status();
+
+    if !status_code.is_success() {
+        return Err(models::LolApiError {
+            description: format!("Unexpected HTTP response code getting summoner. HTTP status code: {}", status_code),
+            http_code: status_code.as_str().to_string()
+        });
+    }
+
+    let result = res.json::<models::Summoner>().await?;
+    return Ok(result);
+}
+
+pub async fn get_summoner_by_puuid(client: &api_fetcher::BoundedHttpFetcher, region: &str, puuid: &str, api_key: &str) -> Result<models::Summoner, models::LolApiError> {
+    let request_url = format!("https://{}.api.riotgames.com/lol/summoner/v4/summoners/by-puuid/{}?api_key={}", region, puuid, api_key);
+    let res = api_fetcher::get_request(client, request_url).await?;
+    let status_code = res.status();
+
+    if !status_code.is_success() {
+        return Err(models::LolApiError {
+            description: format!("Unexpected HTTP response code getting summoner. HTTP status code: {}", status_code),
+            http_code: status_code.as_str().to_string()
+        });
+    }
+
+    let result = res.json::<models::Summoner>().await?;
+    return Ok(result);
+}
+
 pub async fn get_solo_queue_ranking(
     client: &api_fetcher::BoundedHttpFetcher,
     region: &str,
@@ -73,15 +114,83 @@ pub async fn get_league_rankings(
 }
 
 /**
- * Returns the list of IDs of the games the given player (by puuid) has played in over the given period of days
+ * Returns the game the player (by puuid) is currently playing, or None if they aren't in a game
+ */
+pub async fn get_active_game(client: &api_fetcher::BoundedHttpFetcher, region: &str, puuid: &str, api_key: &str) -> Result<Option<models::CurrentGameInfo>, models::LolApiError> {
+    let request_url = format!("https://{}.api.riotgames.com/lol/spectator/v5/active-games/by-summoner/{}?api_key={}", region, puuid, api_key);
+    let res = api_fetcher::get_request(client, request_url).await?;
+    let status_code = res.status();
+
+    // The spectator API responds with a 404 when the player isn't in a game
+    if status_code.as_u16() == 404 {
+        return Ok(None);
+    }
+
+    if !status_code.is_success() {
+        return Err(models::LolApiError {
+            description: format!("Unexpected HTTP response code getting active game. HTTP status code: {}", status_code),
+            http_code: status_code.as_str().to_string()
+        });
+    }
+
+    let result = res.json::<models::CurrentGameInfo>().await?;
+    return Ok(Some(result));
+}
+
+/**
+ * Returns the player's (by puuid) mastery of each champion they have played, highest mastery points first
+ */
+pub async fn get_champion_masteries(client: &api_fetcher::BoundedHttpFetcher, region: &str, puuid: &str, api_key: &str) -> Result<Vec<models::ChampionMastery>, models::LolApiError> {
+    let request_url = format!("https://{}.api.riotgames.com/lol/champion-mastery/v4/champion-masteries/by-puuid/{}?api_key={}", region, puuid, api_key);
+    let res = api_fetcher::get_request(client, request_url).await?;
+    let status_code = res.status();
+
+    if !status_code.is_success() {
+        return Err(models::LolApiError {
+            description: format!("Unexpected HTTP response code getting champion masteries. HTTP status code: {}", status_code),
+            http_code: status_code.as_str().to_string()
+        });
+    }
+
+    let result = res.json::<Vec<models::ChampionMastery>>().await?;
+    return Ok(result);
+}
+
+/**
+ * Returns the player's (by puuid) mastery of the given champion, or None if they have never played it
+ */
+pub async fn get_champion_mastery(client: &api_fetcher::BoundedHttpFetcher, region: &str, puuid: &str, champion_id: u64, api_key: &str) -> Result<Option<models::ChampionMastery>, models::LolApiError> {
+    let request_url = format!("https://{}.api.riotgames.com/lol/champion-mastery/v4/champion-masteries/by-puuid/{}/by-champion/{}?api_key={}", region, puuid, champion_id, api_key);
+    let res = api_fetcher::get_request(client, request_url).await?;
+    let status_code = res.status();
+
+    // The mastery API responds with a 404 when the player has no mastery on the champion
+    if status_code.as_u16() == 404 {
+        return Ok(None);
+    }
+
+    if !status_code.is_success() {
+        return Err(models::LolApiError {
+            description: format!("Unexpected HTTP response code getting champion mastery. HTTP status code: {}", status_code),
+            http_code: status_code.as_str().to_string()
+        });
+    }
+
+    let result = res.json::<models::ChampionMastery>().await?;
+    return Ok(Some(result));
+}
+
+/**
+ * Returns the list of IDs of the games the given player (by puuid) has played in the given range, newest first,
+ * narrowed down by the filter
  */
-pub async fn get_game_ids(client: &api_fetcher::BoundedHttpFetcher, api_key: &str, region: &str, puuid: &str, days: u64, game_type: &Option<String>) -> Result<Vec<String>, models::LolApiError> {
+pub async fn get_game_ids(client: &api_fetcher::BoundedHttpFetcher, api_key: &str, region: &str, puuid: &str, range: &models::DateRange, filter: &models::GameIdsFilter) -> Result<Vec<String>, models::LolApiError> {
 
     let mut game_ids: std::vec::Vec<String> = Vec::new();
     let mut start_index: usize = 0;
     let page_size: usize = 100;
     loop {
-        let request_url = build_game_ids_request_url(region, api_key, puuid, days, game_type, start_index, page_size);
+        let request_url = build_game_ids_request_url(region, api_key, puuid, range, filter, start_index, page_size);
         println!("{}", request_url);
 
         let res = api_fetcher::get_request(client, request_url).await?;
@@ -120,6 +229,23 @@ pub async fn fetch_game_summaries(client: &api_fetcher::BoundedHttpFetcher, api_
     return y.into_iter().collect::<Result<Vec<_>,_>>();
 }
 
+/**
+ * Fetches the summaries of the given games concurrently, yielding each one as soon as it arrives. Unlike
+ * fetch_game_summaries, the summaries are not necessarily in the same order as the given game IDs.
+ */
+pub fn stream_game_summaries<'a>(
+    client: &'a api_fetcher::BoundedHttpFetcher,
+    api_key: &'a str,
+    region: &'a str,
+    puuid: &'a str,
+    game_ids: Vec<String>) -> impl Stream<Item = Result<models::UserGameSummary, models::LolApiError>> + 'a {
+
+    return game_ids
+        .into_iter()
+        .map(move |game_id| async move { get_game_player_summary(client, region, &game_id, puuid, api_key).await })
+        .collect::<FuturesUnordered<_>>();
+}
+
 async fn get_game_player_summary(client: &api_fetcher::BoundedHttpFetcher, region: &str, game_id: &str, puuid: &str, api_key: &str) -> Result<models::UserGameSummary, models::LolApiError> {
     let request_url = format!("https://{}.api.riotgames.com/lol/match/v5/matches/{}?api_key={}", region, game_id, api_key);
 
@@ -130,37 +256,47 @@ async fn get_game_player_summary(client: &api_fetcher::BoundedHttpFetcher, regio
     }
 
     let body = res.json::<models::GameSummary>().await?;
-    let user = find_user_game_summary(puuid, body.info.participants);
+    let user = find_user_game_summary(puuid, &body.info.participants);
 
     match user {
         None => return Err(models::LolApiError {description: "Could not find user summary in match".to_string(), http_code: "500".to_string()}),
-        Some(participant) => return Ok(models::UserGameSummary {game_id: body.info.gameId, match_id: body.metadata.matchId, participant: participant, game_duration_millis: body.info.gameDuration * 1000})
+        Some(participant) => {
+            // Older games don't say when they ended, but we can work it out from when they were created
+            let game_end_timestamp = if body.info.gameEndTimestamp > 0 {
+                body.info.gameEndTimestamp
+            } else {
+                body.info.gameCreation + body.info.gameDuration * 1000
+            };
+
+            return Ok(models::UserGameSummary {
+                game_id: body.info.gameId,
+                match_id: body.metadata.matchId,
+                participant: participant,
+                game_duration_millis: body.info.gameDuration * 1000,
+                game_end_timestamp: game_end_timestamp,
+                participants: body.info.participants
+            });
+        }
     }
 
 }
 
-fn find_user_game_summary(puuid: &str, participants: Vec<models::Participant>) -> Option<models::Participant> {
-    return participants.into_iter().find(|x| x.puuid == puuid );
+fn find_user_game_summary(puuid: &str, participants: &Vec<models::Participant>) -> Option<models::Participant> {
+    return participants.iter().find(|x| x.puuid == puuid ).cloned();
 }
 
 fn build_game_ids_request_url(
     region: &str,
     api_key: &str,
     puuid: &str,
-    days: u64,
-    game_type: &Option<String>,
+    range: &models::DateRange,
+    filter: &models::GameIdsFilter,
     start_index: usize,
     page_size: usize) -> String {
-    
-    let now = SystemTime::now();
-    let end_time = now
-        .duration_since(UNIX_EPOCH)
-        .expect("Time went backwards");
-
-    let start_time = end_time - Duration::new(days * 86400, 0) ;
 
-    let req_url = format!("https://{}.api.riotgames.com/lol/match/v5/matches/by-puuid/{}/ids?api_key={}&count={}&start={}&startTime={}&endTime={}", region, puuid, api_key, page_size, start_index, start_time.as_secs(), end_time.as_secs());
-    let full_url = game_type.as_ref().map(|x| format!("{}&type={}", req_url, x)).unwrap_or(req_url);
+    let req_url = format!("https://{}.api.riotgames.com/lol/match/v5/matches/by-puuid/{}/ids?api_key={}&count={}&start={}&startTime={}&endTime={}", region, puuid, api_key, page_size, start_index, range.start, range.end);
+    let req_url = filter.game_type.as_ref().map(|x| format!("{}&type={}", req_url, x)).unwrap_or(req_url);
+    let full_url = filter.queue.map(|x| format!("{}&queue={}", req_url, x)).unwrap_or(req_url);
 
     return full_url;
 }
@@ -1,6 +1,36 @@
 
 use serde::{Deserialize, Serialize};
 
+/**
+ * A period of time to summarise games over, in seconds since the epoch. The end is exclusive.
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DateRange {
+    pub start: u64,
+    pub end: u64,
+
+    /**
+     * How to describe the period to the user, e.g. 'over the last 7 days' or 'this month'
+     */
+    pub description: String
+}
+
+/**
+ * Narrows down which of a player's games get_game_ids returns. The default is every game.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct GameIdsFilter {
+    /**
+     * Only games of this type, e.g. 'ranked'
+     */
+    pub game_type: Option<String>,
+
+    /**
+     * Only games in this queue (by queue ID)
+     */
+    pub queue: Option<u64>
+}
+
 #[derive(Debug)]
 pub struct LolApiError {
     pub description: String,
@@ -23,24 +53,98 @@ pub struct GameInfo {
     pub participants: Vec<Participant>,
     pub gameDuration: u64,
     pub gameId: u64,
+
+    /**
+     * When the game was created and when it ended, in milliseconds since the epoch. Riot only started sending the
+     * end for games played from patch 11.20.
+     */
+    #[serde(default)]
+    pub gameCreation: u64,
+    #[serde(default)]
+    pub gameEndTimestamp: u64
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Participant {
+    #[serde(default)]
+    pub championId: u64,
     pub championName: String,
     pub puuid: String,
     pub win: bool,
     pub kills: u64,
     pub deaths: u64,
-    pub assists: u64
+    pub assists: u64,
+
+    /**
+     * Lane minions killed
+     */
+    #[serde(default)]
+    pub totalMinionsKilled: u64,
+
+    /**
+     * Jungle monsters killed
+     */
+    #[serde(default)]
+    pub neutralMinionsKilled: u64,
+
+    /**
+     * The position the player was playing, one of TOP, JUNGLE, MIDDLE, BOTTOM or UTILITY. Empty for modes
+     * without positions such as ARAM.
+     */
+    #[serde(default)]
+    pub teamPosition: String,
+
+    /**
+     * 100 for blue side, 200 for red side
+     */
+    #[serde(default)]
+    pub teamId: u64,
+    #[serde(default)]
+    pub totalDamageDealtToChampions: u64,
+    #[serde(default)]
+    pub goldEarned: u64,
+    #[serde(default)]
+    pub visionScore: u64,
+    #[serde(default)]
+    pub doubleKills: u64,
+    #[serde(default)]
+    pub tripleKills: u64,
+    #[serde(default)]
+    pub quadraKills: u64,
+    #[serde(default)]
+    pub pentaKills: u64,
+
+    /**
+     * The player's Riot ID is '<riotIdGameName>#<riotIdTagline>'
+     */
+    #[serde(default)]
+    pub riotIdGameName: String,
+    #[serde(default)]
+    pub riotIdTagline: String
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct UserGameSummary {
     pub game_id: u64,
     pub match_id: String,
     pub participant: Participant,
-    pub game_duration_millis: u64
+    pub game_duration_millis: u64,
+
+    /**
+     * When the game ended, in milliseconds since the epoch
+     */
+    pub game_end_timestamp: u64,
+
+    /**
+     * Everyone in the game, including the player
+     */
+    pub participants: Vec<Participant>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Summoner {
+    pub id: String,
+    pub puuid: String
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -49,7 +153,97 @@ pub struct LeagueEntry {
     pub rank: String,
     pub leaguePoints: u64,
     pub queueType: String,
-    pub leagueId: String
+    #[serde(default)]
+    pub leagueId: String,
+    #[serde(default)]
+    pub wins: u64,
+    #[serde(default)]
+    pub losses: u64,
+    #[serde(default)]
+    pub hotStreak: bool,
+    #[serde(default)]
+    pub veteran: bool,
+    #[serde(default)]
+    pub freshBlood: bool,
+    #[serde(default)]
+    pub inactive: bool,
+
+    /**
+     * Only present while the player is in their promotion series
+     */
+    pub miniSeries: Option<MiniSeries>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MiniSeries {
+    pub wins: u64,
+    pub losses: u64,
+    pub target: u64,
+
+    /**
+     * One character per game in the series: 'W' for a win, 'L' for a loss and 'N' for not yet played
+     */
+    pub progress: String
+}
+
+/**
+ * A game that is currently being played, from the spectator API
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CurrentGameInfo {
+    pub gameId: u64,
+    pub gameMode: String,
+    pub gameType: String,
+
+    /**
+     * When the game started, in milliseconds since the epoch. This is 0 while the players are still loading in.
+     */
+    pub gameStartTime: u64,
+
+    /**
+     * How long the game has been going, in seconds
+     */
+    pub gameLength: u64,
+    pub gameQueueConfigId: Option<u64>,
+    pub participants: Vec<CurrentGameParticipant>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CurrentGameParticipant {
+    pub championId: u64,
+    pub teamId: u64,
+    pub spell1Id: u64,
+    pub spell2Id: u64,
+    pub puuid: Option<String>,
+    pub summonerId: Option<String>,
+
+    /**
+     * The player's Riot ID in 'name#tag' form
+     */
+    pub riotId: Option<String>,
+    #[serde(default)]
+    pub bot: bool
+}
+
+/**
+ * How much a player has played a champion, from the champion mastery API
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChampionMastery {
+    pub championId: u64,
+    pub championLevel: u64,
+    pub championPoints: u64,
+
+    /**
+     * When the player last played the champion, in milliseconds since the epoch
+     */
+    pub lastPlayTime: u64,
+    #[serde(default)]
+    pub championPointsSinceLastLevel: u64,
+    #[serde(default)]
+    pub championPointsUntilNextLevel: u64,
+    #[serde(default)]
+    pub tokensEarned: u64
 }
 
 impl std::convert::From<reqwest::Error> for LolApiError {
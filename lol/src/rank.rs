@@ -0,0 +1,178 @@
+use crate::models;
+
+/**
+ * A human friendly name for a league-v4 queue type, e.g. 'Solo/Duo' for RANKED_SOLO_5x5
+ */
+pub fn queue_display_name(queue_type: &str) -> String {
+    let name = match queue_type {
+        "RANKED_SOLO_5x5" => "Solo/Duo",
+        "RANKED_FLEX_SR" => "Flex",
+        "RANKED_FLEX_TT" => "Flex 3v3",
+        "RANKED_TFT" => "TFT",
+        "RANKED_TFT_DOUBLE_UP" => "TFT Double Up",
+        "RANKED_TFT_TURBO" => "TFT Hyper Roll",
+        other => other
+    };
+
+    return name.to_string();
+}
+
+/**
+ * Orders queues so the ones people care about most come first
+ */
+pub fn queue_display_order(queue_type: &str) -> u64 {
+    match queue_type {
+        "RANKED_SOLO_5x5" => 0,
+        "RANKED_FLEX_SR" => 1,
+        _ => 2
+    }
+}
+
+/**
+ * Turns a tier such as 'GOLD' into 'Gold'
+ */
+pub fn format_tier(tier: &str) -> String {
+    let lower = tier.to_lowercase();
+    let mut characters = lower.chars();
+
+    return match characters.next() {
+        None => "".to_string(),
+        Some(first) => first.to_uppercase().chain(characters).collect()
+    };
+}
+
+/**
+ * The tier and division, e.g. 'Gold II'. Master and above have no divisions, so it's just the tier for those.
+ */
+pub fn format_rank(entry: &models::LeagueEntry) -> String {
+    if is_apex_tier(&entry.tier) {
+        return format_tier(&entry.tier);
+    } else {
+        return format!("{} {}", format_tier(&entry.tier), entry.rank);
+    }
+}
+
+pub fn is_apex_tier(tier: &str) -> bool {
+    return tier == "MASTER" || tier == "GRANDMASTER" || tier == "CHALLENGER";
+}
+
+/**
+ * The percentage of games won, rounded down
+ */
+pub fn winrate_percentage(wins: u64, losses: u64) -> u64 {
+    return (wins * 100).checked_div(wins + losses).unwrap_or(0);
+}
+
+const TIERS: [&str; 10] = ["IRON", "BRONZE", "SILVER", "GOLD", "PLATINUM", "EMERALD", "DIAMOND", "MASTER", "GRANDMASTER", "CHALLENGER"];
+const DIVISIONS: [&str; 4] = ["IV", "III", "II", "I"];
+
+/**
+ * Places the rank on a single LP scale, as if every division were worth 100 LP, so that ranks can be compared
+ * and subtracted. Master, Grandmaster and Challenger share one LP scale starting where Diamond I ends.
+ */
+pub fn absolute_league_points(entry: &models::LeagueEntry) -> i64 {
+    let tier_index = TIERS.iter().position(|tier| *tier == entry.tier).unwrap_or(0) as i64;
+    let league_points = entry.leaguePoints as i64;
+
+    if is_apex_tier(&entry.tier) {
+        let master_index = TIERS.iter().position(|tier| *tier == "MASTER").unwrap_or(0) as i64;
+        return master_index * 400 + league_points;
+    }
+
+    let division_index = DIVISIONS.iter().position(|division| *division == entry.rank).unwrap_or(0) as i64;
+    return tier_index * 400 + division_index * 100 + league_points;
+}
+
+/**
+ * Orders tiers and divisions (ignoring LP) so we can tell promotions from demotions. Apex tiers are ordered by tier.
+ */
+pub fn division_ordinal(entry: &models::LeagueEntry) -> i64 {
+    let tier_index = TIERS.iter().position(|tier| *tier == entry.tier).unwrap_or(0) as i64;
+
+    if is_apex_tier(&entry.tier) {
+        return tier_index * 4;
+    }
+
+    let division_index = DIVISIONS.iter().position(|division| *division == entry.rank).unwrap_or(0) as i64;
+    return tier_index * 4 + division_index;
+}
+
+/**
+ * Describes how the rank changed from the previous entry, e.g. '+62 LP, promoted from Gold III'
+ */
+pub fn format_rank_change(previous: &models::LeagueEntry, current: &models::LeagueEntry) -> String {
+    let change = absolute_league_points(current) - absolute_league_points(previous);
+    let change_string = if change >= 0 {format!("+{} LP", change)} else {format!("{} LP", change)};
+
+    let previous_division = division_ordinal(previous);
+    let current_division = division_ordinal(current);
+
+    if current_division > previous_division {
+        return format!("{}, promoted from {}", change_string, format_rank(previous));
+    } else if current_division < previous_division {
+        return format!("{}, demoted from {}", change_string, format_rank(previous));
+    } else {
+        return change_string;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(tier: &str, rank: &str, league_points: u64) -> models::LeagueEntry {
+        return serde_json::from_value(serde_json::json!({
+            "tier": tier,
+            "rank": rank,
+            "leaguePoints": league_points,
+            "queueType": "RANKED_SOLO_5x5"
+        })).unwrap();
+    }
+
+    #[test]
+    fn places_divisions_from_iv_up_to_i() {
+        assert_eq!(absolute_league_points(&entry("IRON", "IV", 0)), 0);
+        assert_eq!(absolute_league_points(&entry("IRON", "I", 50)), 350);
+        assert_eq!(absolute_league_points(&entry("GOLD", "IV", 0)), 1200);
+        assert!(absolute_league_points(&entry("GOLD", "III", 0)) > absolute_league_points(&entry("GOLD", "IV", 99)));
+        assert!(absolute_league_points(&entry("PLATINUM", "IV", 0)) > absolute_league_points(&entry("GOLD", "I", 99)));
+    }
+
+    #[test]
+    fn puts_apex_tiers_on_one_scale_after_diamond_i() {
+        assert_eq!(absolute_league_points(&entry("MASTER", "I", 0)), absolute_league_points(&entry("DIAMOND", "I", 100)));
+        assert_eq!(absolute_league_points(&entry("GRANDMASTER", "I", 250)), absolute_league_points(&entry("MASTER", "I", 250)));
+        assert_eq!(absolute_league_points(&entry("CHALLENGER", "I", 1000)) - absolute_league_points(&entry("MASTER", "I", 0)), 1000);
+    }
+
+    #[test]
+    fn orders_divisions_and_apex_tiers() {
+        assert!(division_ordinal(&entry("SILVER", "III", 0)) > division_ordinal(&entry("SILVER", "IV", 0)));
+        assert!(division_ordinal(&entry("GOLD", "IV", 0)) > division_ordinal(&entry("SILVER", "I", 0)));
+        assert!(division_ordinal(&entry("MASTER", "I", 0)) > division_ordinal(&entry("DIAMOND", "I", 0)));
+        assert!(division_ordinal(&entry("GRANDMASTER", "I", 0)) > division_ordinal(&entry("MASTER", "I", 500)));
+        assert!(division_ordinal(&entry("CHALLENGER", "I", 0)) > division_ordinal(&entry("GRANDMASTER", "I", 0)));
+    }
+
+    #[test]
+    fn describes_lp_changes_within_a_division() {
+        assert_eq!(format_rank_change(&entry("GOLD", "II", 40), &entry("GOLD", "II", 62)), "+22 LP");
+        assert_eq!(format_rank_change(&entry("GOLD", "II", 40), &entry("GOLD", "II", 21)), "-19 LP");
+        assert_eq!(format_rank_change(&entry("GOLD", "II", 40), &entry("GOLD", "II", 40)), "+0 LP");
+    }
+
+    #[test]
+    fn describes_promotions_and_demotions() {
+        assert_eq!(format_rank_change(&entry("GOLD", "III", 80), &entry("GOLD", "II", 42)), "+62 LP, promoted from Gold III");
+        assert_eq!(format_rank_change(&entry("PLATINUM", "IV", 10), &entry("GOLD", "I", 75)), "-35 LP, demoted from Platinum IV");
+        assert_eq!(format_rank_change(&entry("DIAMOND", "I", 90), &entry("MASTER", "I", 12)), "+22 LP, promoted from Diamond I");
+        assert_eq!(format_rank_change(&entry("GRANDMASTER", "I", 310), &entry("MASTER", "I", 290)), "-20 LP, demoted from Grandmaster");
+    }
+
+    #[test]
+    fn rounds_winrates_down() {
+        assert_eq!(winrate_percentage(2, 1), 66);
+        assert_eq!(winrate_percentage(0, 0), 0);
+        assert_eq!(winrate_percentage(5, 0), 100);
+    }
+}
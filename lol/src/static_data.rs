@@ -0,0 +1,433 @@
+use std::collections::HashMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use tokio::sync::OnceCell;
+use crate::models;
+
+/**
+ * Data Dragon is Riot's CDN for static game data. It isn't rate limited like the API, so we don't go through
+ * the BoundedHttpFetcher for it.
+ */
+const DATA_DRAGON_URL: &str = "https://ddragon.leagueoflegends.com";
+
+/**
+ * Queues aren't part of Data Dragon, Riot publish them alongside the API documentation instead
+ */
+const QUEUES_URL: &str = "https://static.developer.riotgames.com/docs/lol/queues.json";
+
+const CHAMPIONS_FILE: &str = "champion.json";
+const SUMMONER_SPELLS_FILE: &str = "summoner.json";
+const QUEUES_FILE: &str = "queues.json";
+
+static STATIC_DATA: OnceCell<StaticData> = OnceCell::const_new();
+static CHAMPIONS: OnceCell<Vec<StaticEntry>> = OnceCell::const_new();
+
+/**
+ * Where the static data files come from. Either way the files are laid out as '<dir>/<version>/<file>', e.g.
+ * '<dir>/14.8.1/champion.json', so a cache directory can be used as a bundle.
+ */
+#[derive(Debug, Clone)]
+pub enum StaticDataSource {
+    /**
+     * Downloads the latest version's files, keeping them in the cache directory so each file is only downloaded
+     * once per version. If Data Dragon is unreachable the newest cached version is used instead.
+     */
+    Remote { cache_dir: PathBuf },
+
+    /**
+     * Only reads the newest version in the given directory, so nothing is downloaded (e.g. when running offline)
+     */
+    Bundle { path: PathBuf }
+}
+
+/**
+ * A champion or summoner spell
+ */
+#[derive(Debug, Clone)]
+pub struct StaticEntry {
+    pub id: u64,
+
+    /**
+     * Riot's internal name, e.g. 'MonkeyKing' for Wukong
+     */
+    pub key: String,
+    pub name: String,
+    pub image_url: String
+}
+
+#[derive(Debug, Clone)]
+pub struct Queue {
+    pub id: u64,
+    pub map: String,
+    pub name: String
+}
+
+#[derive(Debug)]
+pub struct StaticData {
+    pub version: String,
+    champions: HashMap<u64, StaticEntry>,
+    summoner_spells: HashMap<u64, StaticEntry>,
+    queues: HashMap<u64, Queue>
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct DataDragonFile<T> {
+    data: HashMap<String, T>
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct DataDragonImage {
+    full: String
+}
+
+/**
+ * Champions and summoner spells both have their numeric ID as 'key' and their internal name as 'id'
+ */
+#[derive(Serialize, Deserialize, Debug)]
+struct DataDragonKeyedEntry {
+    id: String,
+    key: String,
+    name: String,
+    image: DataDragonImage
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct QueueDefinition {
+    queueId: u64,
+    map: String,
+    description: Option<String>
+}
+
+/**
+ * Uses the bundle in STATIC_DATA_BUNDLE_PATH if it is set, otherwise downloads into STATIC_DATA_CACHE_DIR (or a
+ * directory in the system's temporary directory)
+ */
+pub fn static_data_source_from_env() -> StaticDataSource {
+    if let Ok(path) = env::var("STATIC_DATA_BUNDLE_PATH") {
+        return StaticDataSource::Bundle { path: PathBuf::from(path) };
+    }
+
+    let cache_dir = env::var("STATIC_DATA_CACHE_DIR")
+        .map(PathBuf::from)
+        .unwrap_or(env::temp_dir().join("lol-static-data"));
+
+    return StaticDataSource::Remote { cache_dir: cache_dir };
+}
+
+/**
+ * The static data from the environment's source, which is only loaded once per process. Failing to load it isn't
+ * remembered, so the next call tries again.
+ */
+pub async fn get_static_data() -> Result<&'static StaticData, models::LolApiError> {
+    return STATIC_DATA.get_or_try_init(|| async {
+        load_static_data(&static_data_source_from_env()).await
+    }).await;
+}
+
+/**
+ * Only the champions from the environment's source, which is all autocomplete needs, so a cold start doesn't
+ * download the rest of the static data
+ */
+pub async fn get_champions() -> Result<&'static Vec<StaticEntry>, models::LolApiError> {
+    return CHAMPIONS.get_or_try_init(|| async {
+        load_champions(&static_data_source_from_env()).await
+    }).await;
+}
+
+pub async fn load_static_data(source: &StaticDataSource) -> Result<StaticData, models::LolApiError> {
+    let (version_directory, version, download) = resolve_version(source).await?;
+    let data_dragon_url = |file: &str| format!("{}/cdn/{}/data/en_US/{}", DATA_DRAGON_URL, version, file);
+
+    let (champions, summoner_spells, queues) = tokio::try_join!(
+        read_file(&version_directory, CHAMPIONS_FILE, download.then(|| data_dragon_url(CHAMPIONS_FILE))),
+        read_file(&version_directory, SUMMONER_SPELLS_FILE, download.then(|| data_dragon_url(SUMMONER_SPELLS_FILE))),
+        read_file(&version_directory, QUEUES_FILE, download.then(|| QUEUES_URL.to_string()))
+    )?;
+
+    let champions = parse_champions(&version, &champions)?.into_iter()
+        .map(|champion| (champion.id, champion))
+        .collect();
+
+    let summoner_spells = parse::<DataDragonFile<DataDragonKeyedEntry>>(SUMMONER_SPELLS_FILE, &summoner_spells)?.data.into_values()
+        .filter_map(|spell| spell.key.parse::<u64>().ok().map(|id| (id, StaticEntry {
+            id: id,
+            image_url: create_image_url(&version, "spell", &spell.image),
+            key: spell.id,
+            name: spell.name
+        })))
+        .collect();
+
+    let queues = parse::<Vec<QueueDefinition>>(QUEUES_FILE, &queues)?.into_iter()
+        .map(|queue| (queue.queueId, Queue {
+            id: queue.queueId,
+            map: queue.map,
+            name: create_queue_name(queue.queueId, &queue.description)
+        }))
+        .collect();
+
+    return Ok(StaticData {
+        version: version,
+        champions: champions,
+        summoner_spells: summoner_spells,
+        queues: queues
+    });
+}
+
+pub async fn load_champions(source: &StaticDataSource) -> Result<Vec<StaticEntry>, models::LolApiError> {
+    let (version_directory, version, download) = resolve_version(source).await?;
+    let url = format!("{}/cdn/{}/data/en_US/{}", DATA_DRAGON_URL, version, CHAMPIONS_FILE);
+    let champions = read_file(&version_directory, CHAMPIONS_FILE, download.then_some(url)).await?;
+
+    return parse_champions(&version, &champions);
+}
+
+impl StaticData {
+    pub fn champion(&self, champion_id: u64) -> Option<&StaticEntry> {
+        return self.champions.get(&champion_id);
+    }
+
+    pub fn champions(&self) -> impl Iterator<Item = &StaticEntry> {
+        return self.champions.values();
+    }
+
+    /**
+     * Finds the champion by name, ignoring case and punctuation so that e.g. 'kaisa' finds Kai'Sa
+     */
+    pub fn find_champion(&self, name: &str) -> Option<&StaticEntry> {
+        let wanted = normalise_name(name);
+
+        return self.champions.values().find(|champion| normalise_name(&champion.name) == wanted);
+    }
+
+    pub fn champion_name(&self, champion_id: u64) -> String {
+        return name_or_fallback(self.champions.get(&champion_id), "Champion", champion_id);
+    }
+
+    pub fn summoner_spell(&self, spell_id: u64) -> Option<&StaticEntry> {
+        return self.summoner_spells.get(&spell_id);
+    }
+
+    pub fn summoner_spell_name(&self, spell_id: u64) -> String {
+        return name_or_fallback(self.summoner_spells.get(&spell_id), "Spell", spell_id);
+    }
+
+    pub fn queue(&self, queue_id: u64) -> Option<&Queue> {
+        return self.queues.get(&queue_id);
+    }
+
+    /**
+     * A human friendly name for a match queue ID, e.g. 'Ranked Solo/Duo' for 420
+     */
+    pub fn queue_name(&self, queue_id: u64) -> String {
+        return self.queues.get(&queue_id)
+            .map(|queue| queue.name.to_string())
+            .unwrap_or(format!("Queue {}", queue_id));
+    }
+}
+
+pub async fn get_latest_version() -> Result<String, models::LolApiError> {
+    let versions = reqwest::get(format!("{}/api/versions.json", DATA_DRAGON_URL)).await?.json::<Vec<String>>().await?;
+
+    return versions.into_iter().next().ok_or(static_data_error("No Data Dragon versions".to_string()));
+}
+
+/**
+ * The directory to read the version's files from, the version, and whether missing files should be downloaded
+ */
+async fn resolve_version(source: &StaticDataSource) -> Result<(PathBuf, String, bool), models::LolApiError> {
+    return match source {
+        StaticDataSource::Bundle { path } => {
+            let version = find_newest_version(path).await.ok_or(static_data_error(format!("No static data versions in {}", path.display())))?;
+            Ok((path.join(&version), version, false))
+        },
+        StaticDataSource::Remote { cache_dir } => {
+            let version = match get_latest_version().await {
+                Ok(version) => version,
+                Err(err) => {
+                    println!("Could not get latest Data Dragon version, falling back to the cache: {:?}", err);
+                    find_newest_version(cache_dir).await.ok_or(err)?
+                }
+            };
+
+            Ok((cache_dir.join(&version), version, true))
+        }
+    };
+}
+
+/**
+ * Reads the file from the version's directory. If it isn't there and a URL is given, it is downloaded and saved
+ * there for next time.
+ */
+async fn read_file(directory: &Path, file: &str, url: Option<String>) -> Result<String, models::LolApiError> {
+    let path = directory.join(file);
+
+    if let Ok(contents) = tokio::fs::read_to_string(&path).await {
+        return Ok(contents);
+    }
+
+    let url = url.ok_or(static_data_error(format!("Missing static data file {}", path.display())))?;
+    let res = reqwest::get(url).await?;
+    let status_code = res.status();
+
+    if !status_code.is_success() {
+        return Err(models::LolApiError {
+            description: format!("Unexpected HTTP response code getting static data file {}. HTTP status code: {}", file, status_code),
+            http_code: status_code.as_str().to_string()
+        });
+    }
+
+    let contents = res.text().await?;
+
+    // The cache is only an optimisation, so carry on if we can't write to it
+    let write_result = match tokio::fs::create_dir_all(directory).await {
+        Ok(()) => tokio::fs::write(&path, &contents).await,
+        Err(err) => Err(err)
+    };
+
+    if let Err(err) = write_result {
+        println!("Could not cache static data file {}: {}", path.display(), err);
+    }
+
+    return Ok(contents);
+}
+
+/**
+ * The newest version with a directory in the given directory, comparing versions numerically (so 14.10 is newer
+ * than 14.9)
+ */
+async fn find_newest_version(directory: &Path) -> Option<String> {
+    let mut entries = tokio::fs::read_dir(directory).await.ok()?;
+    let mut versions: Vec<String> = Vec::new();
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        if entry.path().is_dir() {
+            versions.push(entry.file_name().to_string_lossy().to_string());
+        }
+    }
+
+    return versions.into_iter().max_by_key(|version| {
+        version.split('.').map(|part| part.parse::<u64>().unwrap_or(0)).collect::<Vec<u64>>()
+    });
+}
+
+fn parse_champions(version: &str, contents: &str) -> Result<Vec<StaticEntry>, models::LolApiError> {
+    return Ok(parse::<DataDragonFile<DataDragonKeyedEntry>>(CHAMPIONS_FILE, contents)?.data.into_values()
+        .filter_map(|champion| champion.key.parse::<u64>().ok().map(|id| StaticEntry {
+            id: id,
+            image_url: create_image_url(version, "champion", &champion.image),
+            key: champion.id,
+            name: champion.name
+        }))
+        .collect());
+}
+
+fn create_image_url(version: &str, kind: &str, image: &DataDragonImage) -> String {
+    return format!("{}/cdn/{}/img/{}/{}", DATA_DRAGON_URL, version, kind, image.full);
+}
+
+fn parse<'a, T: Deserialize<'a>>(file: &str, contents: &'a str) -> Result<T, models::LolApiError> {
+    return serde_json::from_str::<T>(contents).map_err(|err| static_data_error(format!("Could not parse static data file {}: {}", file, err)));
+}
+
+/**
+ * Riot describe queues like '5v5 Ranked Solo games', so drop the 'games'. The most common queues get the names
+ * players know them by instead.
+ */
+fn create_queue_name(queue_id: u64, description: &Option<String>) -> String {
+    let name = match queue_id {
+        0 => "Custom",
+        400 => "Normal Draft",
+        420 => "Ranked Solo/Duo",
+        430 => "Normal Blind",
+        440 => "Ranked Flex",
+        450 => "ARAM",
+        490 => "Quickplay",
+        700 => "Clash",
+        _ => return match description {
+            None => format!("Queue {}", queue_id),
+            Some(description) => description.trim_end_matches(" games").to_string()
+        }
+    };
+
+    return name.to_string();
+}
+
+fn name_or_fallback(entry: Option<&StaticEntry>, kind: &str, id: u64) -> String {
+    return entry.map(|entry| entry.name.to_string()).unwrap_or(format!("{} {}", kind, id));
+}
+
+/**
+ * Lower case with only letters and numbers, so that names can be compared however they were typed
+ */
+pub fn normalise_name(name: &str) -> String {
+    return name.chars().filter(|character| character.is_alphanumeric()).flat_map(char::to_lowercase).collect();
+}
+
+fn static_data_error(description: String) -> models::LolApiError {
+    return models::LolApiError {
+        description: description,
+        http_code: "500".to_string()
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture_bundle() -> StaticDataSource {
+        return StaticDataSource::Bundle { path: Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/static_data") };
+    }
+
+    #[tokio::test]
+    async fn loads_the_newest_version_in_a_bundle() {
+        let static_data = load_static_data(&fixture_bundle()).await.unwrap();
+
+        assert_eq!(static_data.version, "14.10.1");
+        assert_eq!(static_data.champions().count(), 3);
+    }
+
+    #[tokio::test]
+    async fn looks_up_names_with_a_fallback() {
+        let static_data = load_static_data(&fixture_bundle()).await.unwrap();
+
+        assert_eq!(static_data.champion_name(62), "Wukong");
+        assert_eq!(static_data.champion_name(9999), "Champion 9999");
+        assert_eq!(static_data.summoner_spell_name(4), "Flash");
+        assert_eq!(static_data.summoner_spell_name(9999), "Spell 9999");
+        assert_eq!(static_data.champion(145).unwrap().image_url, "https://ddragon.leagueoflegends.com/cdn/14.10.1/img/champion/Kaisa.png");
+    }
+
+    #[tokio::test]
+    async fn names_queues() {
+        let static_data = load_static_data(&fixture_bundle()).await.unwrap();
+
+        assert_eq!(static_data.queue_name(420), "Ranked Solo/Duo");
+        assert_eq!(static_data.queue_name(1700), "Arena");
+        assert_eq!(static_data.queue_name(9999), "Queue 9999");
+    }
+
+    #[tokio::test]
+    async fn finds_champions_ignoring_case_and_punctuation() {
+        let static_data = load_static_data(&fixture_bundle()).await.unwrap();
+
+        assert_eq!(static_data.find_champion("kaisa").map(|champion| champion.id), Some(145));
+        assert_eq!(static_data.find_champion("KAI'SA").map(|champion| champion.id), Some(145));
+        assert!(static_data.find_champion("monkeyking").is_none());
+    }
+
+    #[tokio::test]
+    async fn loads_only_champions() {
+        let mut names: Vec<String> = load_champions(&fixture_bundle()).await.unwrap().into_iter().map(|champion| champion.name).collect();
+        names.sort();
+
+        assert_eq!(names, vec!["Annie", "Kai'Sa", "Wukong"]);
+    }
+
+    #[tokio::test]
+    async fn fails_when_a_bundle_has_no_versions() {
+        let source = StaticDataSource::Bundle { path: Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/missing") };
+
+        assert!(load_static_data(&source).await.is_err());
+    }
+}
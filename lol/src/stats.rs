@@ -0,0 +1,32 @@
+/**
+ * Kills and assists per death. No deaths counts as one, so a deathless game's KDA is its kills plus assists.
+ */
+pub fn kda(kills: u64, deaths: u64, assists: u64) -> f64 {
+    return (kills + assists) as f64 / deaths.max(1) as f64;
+}
+
+/**
+ * Minions and jungle monsters killed per minute played, or 0 if no time was played
+ */
+pub fn creep_score_per_minute(creep_score: u64, played_millis: u64) -> f64 {
+    let minutes_played = played_millis as f64 / 60000.0;
+    return if minutes_played > 0.0 {creep_score as f64 / minutes_played} else {0.0};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_no_deaths_as_one() {
+        assert_eq!(kda(10, 2, 8), 9.0);
+        assert_eq!(kda(3, 0, 4), 7.0);
+        assert_eq!(kda(0, 0, 0), 0.0);
+    }
+
+    #[test]
+    fn divides_creep_score_by_minutes_played() {
+        assert_eq!(creep_score_per_minute(210, 30 * 60000), 7.0);
+        assert_eq!(creep_score_per_minute(210, 0), 0.0);
+    }
+}
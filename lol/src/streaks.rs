@@ -0,0 +1,139 @@
+use crate::models;
+
+/**
+ * Fewer games than this in a row isn't worth calling a streak
+ */
+const MIN_STREAK_GAMES: usize = 3;
+
+/**
+ * Dying at least this many times in a game counts towards a streak of bad games
+ */
+const HIGH_DEATHS: u64 = 8;
+
+/**
+ * How many of the latest games the average K/D/A is worked out over
+ */
+const RECENT_GAMES: usize = 3;
+
+/**
+ * Describes the streaks the player is on as of their latest game, e.g.
+ * 'On a 5-game losing streak, died 8+ times in each of the last 3 games, last 3 games 0.7/9.3/2.0 average'.
+ * None if they aren't on any streak. The games can be in any order.
+ */
+pub fn describe_streaks(games: &[models::UserGameSummary]) -> Option<String> {
+    let mut newest_first: Vec<&models::UserGameSummary> = games.iter().collect();
+    newest_first.sort_by_key(|game| std::cmp::Reverse(game.game_end_timestamp));
+
+    let latest = newest_first.first()?;
+    let result_streak = newest_first.iter().take_while(|game| game.participant.win == latest.participant.win).count();
+    let deaths_streak = newest_first.iter().take_while(|game| game.participant.deaths >= HIGH_DEATHS).count();
+
+    let mut streaks: Vec<String> = Vec::new();
+
+    if result_streak >= MIN_STREAK_GAMES {
+        streaks.push(format!("On a {}-game {} streak", result_streak, if latest.participant.win {"winning"} else {"losing"}));
+    }
+
+    if deaths_streak >= MIN_STREAK_GAMES {
+        let description = format!("died {}+ times in each of the last {} games", HIGH_DEATHS, deaths_streak);
+        streaks.push(if streaks.is_empty() {capitalise(&description)} else {description});
+    }
+
+    if streaks.is_empty() {
+        return None;
+    }
+
+    let recent: Vec<&&models::UserGameSummary> = newest_first.iter().take(RECENT_GAMES).collect();
+    let average = |stat: fn(&models::Participant) -> u64| recent.iter().map(|game| stat(&game.participant)).sum::<u64>() as f64 / recent.len() as f64;
+
+    streaks.push(format!(
+        "last {} games {:.1}/{:.1}/{:.1} average",
+        recent.len(),
+        average(|participant| participant.kills),
+        average(|participant| participant.deaths),
+        average(|participant| participant.assists)
+    ));
+
+    return Some(streaks.join(", "));
+}
+
+fn capitalise(text: &str) -> String {
+    let mut characters = text.chars();
+
+    return match characters.next() {
+        None => "".to_string(),
+        Some(first) => first.to_uppercase().chain(characters).collect()
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /**
+     * A game that ended at the given time, oldest games having the smallest times
+     */
+    fn game(game_end_timestamp: u64, win: bool, kills: u64, deaths: u64, assists: u64) -> models::UserGameSummary {
+        let participant: models::Participant = serde_json::from_value(serde_json::json!({
+            "championName": "Ahri",
+            "puuid": "puuid",
+            "win": win,
+            "kills": kills,
+            "deaths": deaths,
+            "assists": assists
+        })).unwrap();
+
+        return models::UserGameSummary {
+            game_id: game_end_timestamp,
+            match_id: format!("EUW1_{}", game_end_timestamp),
+            participant: participant.clone(),
+            game_duration_millis: 1800000,
+            game_end_timestamp: game_end_timestamp,
+            participants: vec![participant]
+        };
+    }
+
+    #[test]
+    fn describes_a_losing_streak() {
+        let games = vec![game(1, true, 9, 0, 9), game(2, false, 1, 5, 2), game(3, false, 0, 4, 3), game(4, false, 2, 6, 1)];
+
+        assert_eq!(describe_streaks(&games), Some("On a 3-game losing streak, last 3 games 1.0/5.0/2.0 average".to_string()));
+    }
+
+    #[test]
+    fn describes_a_winning_streak_whatever_order_the_games_are_in() {
+        let games = vec![game(4, true, 5, 1, 5), game(1, false, 0, 9, 0), game(3, true, 7, 2, 4), game(2, true, 6, 3, 6), game(5, true, 9, 0, 3)];
+
+        assert_eq!(describe_streaks(&games), Some("On a 4-game winning streak, last 3 games 7.0/1.0/4.0 average".to_string()));
+    }
+
+    #[test]
+    fn describes_a_high_deaths_streak() {
+        let games = vec![game(1, true, 2, 8, 4), game(2, false, 1, 10, 2), game(3, true, 3, 9, 6)];
+
+        assert_eq!(describe_streaks(&games), Some("Died 8+ times in each of the last 3 games, last 3 games 2.0/9.0/4.0 average".to_string()));
+    }
+
+    #[test]
+    fn describes_both_streaks_together() {
+        let games = vec![game(1, false, 0, 8, 1), game(2, false, 1, 8, 1), game(3, false, 2, 11, 1)];
+
+        assert_eq!(
+            describe_streaks(&games),
+            Some("On a 3-game losing streak, died 8+ times in each of the last 3 games, last 3 games 1.0/9.0/1.0 average".to_string())
+        );
+    }
+
+    #[test]
+    fn ignores_streaks_shorter_than_the_minimum() {
+        // Two losses in a row, and seven deaths is one short of counting towards a third bad game
+        let games = vec![game(1, true, 0, 7, 0), game(2, false, 0, 8, 0), game(3, false, 0, 8, 0)];
+
+        assert_eq!(describe_streaks(&games), None);
+    }
+
+    #[test]
+    fn describes_nothing_without_games() {
+        assert_eq!(describe_streaks(&[]), None);
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::HashSet;
+use std::env;
+use tokio::time::{timeout_at, Instant};
+use common::linked_accounts::LinkedAccount;
+use crate::models;
+use crate::played_games;
+
+/**
+ * Refreshes the rank and weekly stats of everyone who has linked their account in a server, which /leaderboard
+ * reads from. Players are refreshed least recently refreshed first, so anyone skipped at the deadline goes first next time.
+ */
+pub async fn refresh_leaderboards(toolbox: &models::Toolbox, deadline: Instant) {
+    let api_key = match env::var("LOL_API_KEY") {
+        Ok(key) => key,
+        Err(_) => {
+            println!("Missing LOL API key");
+            return;
+        }
+    };
+
+    let guild_ids = match common::linked_accounts::get_linked_guilds(toolbox.storage.as_ref()).await {
+        Ok(guild_ids) => guild_ids,
+        Err(err) => {
+            println!("Could not get linked guilds: {:?}", err);
+            return;
+        }
+    };
+
+    // The same account can be linked in several servers, but only needs refreshing once
+    let mut accounts: Vec<LinkedAccount> = Vec::new();
+    let mut seen_puuids: HashSet<String> = HashSet::new();
+
+    for guild_id in guild_ids.iter() {
+        match common::linked_accounts::get_linked_accounts(toolbox.storage.as_ref(), guild_id).await {
+            Err(err) => println!("Could not get linked accounts for guild {}: {:?}", guild_id, err),
+            Ok(guild_accounts) => {
+                accounts.extend(guild_accounts.into_iter().filter(|account| seen_puuids.insert(account.puuid.to_string())));
+            }
+        }
+    }
+
+    let mut accounts_by_refresh: Vec<(u64, LinkedAccount)> = Vec::new();
+    for account in accounts.into_iter() {
+        let updated_at = match common::player_stats::get_weekly_stats(toolbox.storage.as_ref(), &account.puuid).await {
+            Ok(stats) => stats.map(|stats| stats.updated_at).unwrap_or(0),
+            Err(err) => {
+                println!("Could not get weekly stats for {}: {:?}", account.player_name, err);
+                0
+            }
+        };
+        accounts_by_refresh.push((updated_at, account));
+    }
+    accounts_by_refresh.sort_by_key(|(updated_at, _)| *updated_at);
+
+    println!("Refreshing leaderboard data for {} players in {} guilds", accounts_by_refresh.len(), guild_ids.len());
+
+    for (index, (_, account)) in accounts_by_refresh.iter().enumerate() {
+        match timeout_at(deadline, refresh_player(toolbox, &api_key, account)).await {
+            Ok(Ok(())) => {},
+            Ok(Err(err)) => println!("Could not refresh leaderboard data for {}: {}", account.player_name, err),
+            Err(_elapsed) => {
+                println!("Ran out of time, leaving {} players for the next refresh", accounts_by_refresh.len() - index);
+                return;
+            }
+        }
+    }
+}
+
+async fn refresh_player(toolbox: &models::Toolbox, api_key: &str, account: &LinkedAccount) -> Result<(), String> {
+    let storage = toolbox.storage.as_ref();
+    let now = common::date_range::now_seconds().map_err(|err| format!("{}", err))?;
+
+    let summoner = lol::get_summoner_by_puuid(&toolbox.lol_api_fetcher, "euw1", &account.puuid, api_key).await
+        .map_err(|err| format!("{:?}", err))?;
+
+    // Tracking the player means the rank snapshot job keeps their rank history going between refreshes too
+    common::rank_history::track_player(storage, &account.puuid, &summoner.id, &account.player_name).await
+        .map_err(|err| format!("{:?}", err))?;
+
+    let entries = lol::get_league_rankings(&toolbox.lol_api_fetcher, "euw1", summoner.id.to_string(), api_key).await
+        .map_err(|err| format!("{:?}", err))?;
+    common::rank_history::store_rank_snapshots(storage, &account.puuid, &entries).await
+        .map_err(|err| format!("{:?}", err))?;
+
+    let games = played_games::sync_played_games(toolbox, api_key, &account.puuid, &common::date_range::last_days(7, now)).await?;
+
+    let stats = common::player_stats::WeeklyStats {
+        puuid: account.puuid.to_string(),
+        games: games.len() as u64,
+        wins: games.iter().filter(|game| game.win).count() as u64,
+        played_for_millis: games.iter().map(|game| game.duration_millis).sum(),
+        updated_at: now
+    };
+
+    common::player_stats::store_weekly_stats(storage, &stats).await
+        .map_err(|err| format!("{:?}", err))?;
+
+    println!("Refreshed leaderboard data for {}: {} games this week", account.player_name, stats.games);
+    return Ok(());
+}
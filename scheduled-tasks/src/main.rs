@@ -0,0 +1,68 @@
+use lambda_runtime::{service_fn, LambdaEvent, Error};
+use serde_json::{json, Value};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::Instant;
+
+use lol;
+use common;
+mod leaderboard_refresh;
+mod match_poller;
+mod models;
+mod played_games;
+mod playtime_budgets;
+mod rank_announcements;
+mod rank_snapshots;
+mod weekly_digest;
+
+/**
+ * How long before the Lambda deadline jobs stop starting new work, so they can finish what they're doing
+ */
+const JOB_DEADLINE_MARGIN: Duration = Duration::from_secs(15);
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    let storage = common::storage::create_storage(common::storage::storage_config_from_env()?).await?;
+
+    let api_fetcher: lol::api_fetcher::BoundedHttpFetcher = lol::api_fetcher::create_lol_client(20,100);
+    let toolbox = models::Toolbox {
+        lol_api_fetcher: api_fetcher,
+        storage: storage,
+        discord_client: common::discord_rest::create_discord_client_from_env()
+    };
+
+    let func = service_fn(|x| func(&toolbox, x));
+    lambda_runtime::run(func).await?;
+    Ok(())
+}
+
+async fn func(toolbox: &models::Toolbox, lambda_event: LambdaEvent<models::ScheduledEvent>) -> Result<Value, serde_json::Error> {
+    let (event, context) = lambda_event.into_parts();
+    println!("Received: {:?}", event);
+
+    let lambda_deadline = to_instant(context.deadline);
+    let deadline = lambda_deadline.checked_sub(JOB_DEADLINE_MARGIN).unwrap_or(lambda_deadline);
+
+    match event.job.as_str() {
+        "rank_snapshots" => rank_snapshots::take_rank_snapshots(toolbox).await,
+        "leaderboard_refresh" => leaderboard_refresh::refresh_leaderboards(toolbox, deadline).await,
+        "rank_announcements" => rank_announcements::announce_rank_changes(toolbox).await,
+        "weekly_digest" => weekly_digest::post_weekly_digests(toolbox, deadline).await,
+        "match_poller" => {
+            match_poller::poll_tracked_matches(toolbox).await;
+            playtime_budgets::check_playtime_budgets(toolbox).await;
+        },
+        job => println!("Unrecognised job {}", job)
+    }
+
+    return Ok(json!({}));
+}
+
+/**
+ * Converts the Lambda deadline, in milliseconds since the epoch, to an instant tokio can time out at
+ */
+fn to_instant(deadline_epoch_millis: u64) -> Instant {
+    let deadline = UNIX_EPOCH + Duration::from_millis(deadline_epoch_millis);
+    let remaining = deadline.duration_since(SystemTime::now()).unwrap_or(Duration::from_secs(0));
+
+    return Instant::now() + remaining;
+}
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::env;
+use common::discord_rest::MessagePayload;
+use common::match_tracking::MatchTrackedPlayer;
+use crate::models;
+
+/**
+ * How far back we look for games the player has finished since we last checked. The poller runs far more often than
+ * this, so it only matters if it stops running for a while.
+ */
+const LOOKBACK_DAYS: u64 = 2;
+
+/**
+ * At most this many new games are posted for a player at once, so a backlog doesn't flood the channel
+ */
+const MAX_NEW_GAMES: usize = 5;
+
+/**
+ * How many of the player's latest games we look at for streaks. This includes the new games, so it must be at
+ * least as many as we post at once.
+ */
+const STREAK_GAMES: usize = 10;
+
+/**
+ * Posts each game that a player tracked with /track has finished since we last checked to the channels tracking
+ * them. Players are checked one after another so that we stay well within the Riot API limits the fetcher enforces.
+ */
+pub async fn poll_tracked_matches(toolbox: &models::Toolbox) {
+    let api_key = match env::var("LOL_API_KEY") {
+        Ok(key) => key,
+        Err(_) => {
+            println!("Missing LOL API key");
+            return;
+        }
+    };
+
+    let tracked = match common::match_tracking::get_match_tracked_players(toolbox.storage.as_ref()).await {
+        Ok(tracked) => tracked,
+        Err(err) => {
+            println!("Could not get players tracked for matches: {:?}", err);
+            return;
+        }
+    };
+
+    // A player tracked in several channels is only checked once
+    let mut channels_by_puuid: HashMap<String, Vec<MatchTrackedPlayer>> = HashMap::new();
+    for player in tracked.into_iter() {
+        channels_by_puuid.entry(player.puuid.to_string()).or_insert(Vec::new()).push(player);
+    }
+
+    println!("Checking {} players for finished games", channels_by_puuid.len());
+
+    for (puuid, channels) in channels_by_puuid.iter() {
+        if let Err(err) = poll_player(toolbox, &api_key, puuid, channels).await {
+            println!("Could not check {} for finished games: {}", channels[0].player_name, err);
+        }
+    }
+}
+
+async fn poll_player(toolbox: &models::Toolbox, api_key: &str, puuid: &str, channels: &Vec<MatchTrackedPlayer>) -> Result<(), String> {
+    let storage = toolbox.storage.as_ref();
+    let player_name = &channels[0].player_name;
+    let now = common::date_range::now_seconds().map_err(|err| format!("{}", err))?;
+
+    let last_seen = common::match_tracking::get_last_seen_match(storage, puuid).await.map_err(|err| format!("{:?}", err))?;
+    let game_ids = lol::get_game_ids(&toolbox.lol_api_fetcher, api_key, "europe", puuid, &common::date_range::last_days(LOOKBACK_DAYS, now), &lol::models::GameIdsFilter::default()).await
+        .map_err(|err| format!("{:?}", err))?;
+
+    let last_seen = match last_seen {
+        Some(last_seen) => last_seen,
+        None => {
+            // We've no idea which games have been posted, so start from the latest one rather than posting old games
+            if let Some(latest) = game_ids.first() {
+                common::match_tracking::store_last_seen_match(storage, puuid, latest).await.map_err(|err| format!("{:?}", err))?;
+            }
+            return Ok(());
+        }
+    };
+
+    // Game IDs are newest first
+    let new_game_ids: Vec<String> = game_ids.iter()
+        .take_while(|game_id| **game_id != last_seen)
+        .take(MAX_NEW_GAMES)
+        .cloned()
+        .collect();
+
+    if new_game_ids.is_empty() {
+        return Ok(());
+    }
+
+    println!("{} has finished {} new games", player_name, new_game_ids.len());
+
+    // The games before the new ones are only needed to spot streaks
+    let recent_game_ids: Vec<String> = game_ids.iter().take(STREAK_GAMES).cloned().collect();
+    let recent_games = lol::fetch_game_summaries(&toolbox.lol_api_fetcher, api_key, "europe", puuid, recent_game_ids).await
+        .map_err(|err| format!("{:?}", err))?;
+
+    let mut games: Vec<&lol::models::UserGameSummary> = recent_games.iter()
+        .filter(|game| new_game_ids.contains(&game.match_id))
+        .collect();
+    games.sort_by_key(|game| game.game_end_timestamp);
+
+    // Failing to get the rank shouldn't stop us posting the games
+    let rank_change = get_rank_change(toolbox, api_key, puuid).await.unwrap_or_else(|err| {
+        println!("Could not get rank change for {}: {}", player_name, err);
+        None
+    });
+
+    for (index, game) in games.iter().enumerate() {
+        let mut message = create_game_message(player_name, game);
+
+        // The rank change is for all the new games together, and the streaks are as of the latest game, so they go
+        // with the latest
+        if index == games.len() - 1 {
+            if let Some(rank_change) = &rank_change {
+                message.push_str(&format!("\n{}", rank_change));
+            }
+
+            if let Some(streaks) = lol::streaks::describe_streaks(&recent_games) {
+                message.push_str(&format!("\n{}", streaks));
+            }
+        }
+
+        for channel in channels.iter() {
+            let post_result = toolbox.discord_client.create_channel_message(&channel.channel_id, &MessagePayload::from_content(&message)).await;
+            println!("Posted {} for {} in channel {}: {:?}", game.match_id, player_name, channel.channel_id, post_result);
+        }
+    }
+
+    return common::match_tracking::store_last_seen_match(storage, puuid, &game_ids[0]).await.map_err(|err| format!("{:?}", err));
+}
+
+/**
+ * How the player's ranks changed since their last snapshot, e.g. 'Solo/Duo: +21 LP (now Gold II 45 LP)', or None if
+ * they didn't change. Takes a new snapshot too.
+ */
+async fn get_rank_change(toolbox: &models::Toolbox, api_key: &str, puuid: &str) -> Result<Option<String>, String> {
+    let storage = toolbox.storage.as_ref();
+    let summoner = lol::get_summoner_by_puuid(&toolbox.lol_api_fetcher, "euw1", puuid, api_key).await
+        .map_err(|err| format!("{:?}", err))?;
+    let entries = lol::get_league_rankings(&toolbox.lol_api_fetcher, "euw1", summoner.id.to_string(), api_key).await
+        .map_err(|err| format!("{:?}", err))?;
+
+    let mut changes: Vec<String> = Vec::new();
+
+    for entry in entries.iter() {
+        let previous = common::rank_history::get_latest_rank_snapshot(storage, puuid, &entry.queueType).await
+            .map_err(|err| format!("{:?}", err))?;
+
+        if let Some(previous) = previous {
+            if lol::rank::absolute_league_points(&previous.entry) != lol::rank::absolute_league_points(entry) {
+                changes.push(format!(
+                    "{}: {} (now {} {} LP)",
+                    lol::rank::queue_display_name(&entry.queueType),
+                    lol::rank::format_rank_change(&previous.entry, entry),
+                    lol::rank::format_rank(entry),
+                    entry.leaguePoints
+                ));
+            }
+        }
+    }
+
+    common::rank_history::store_rank_snapshots(storage, puuid, &entries).await.map_err(|err| format!("{:?}", err))?;
+
+    return Ok(if changes.is_empty() {None} else {Some(changes.join(", "))});
+}
+
+/**
+ * e.g. '**Bob#EUW** won a game as Ahri: 10/2/8 (9.00 KDA) in 31 minutes'
+ */
+fn create_game_message(player_name: &str, game: &lol::models::UserGameSummary) -> String {
+    let participant = &game.participant;
+    let kda = lol::stats::kda(participant.kills, participant.deaths, participant.assists);
+
+    return format!(
+        "**{}** {} a game as {}: {}/{}/{} ({:.2} KDA) in {} minutes",
+        player_name,
+        if participant.win {"won"} else {"lost"},
+        participant.championName,
+        participant.kills,
+        participant.deaths,
+        participant.assists,
+        kda,
+        game.game_duration_millis / 60000
+    );
+}
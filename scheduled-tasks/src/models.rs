@@ -0,0 +1,17 @@
+use lol;
+use common::storage::BotStorage;
+use serde::{Deserialize, Serialize};
+
+pub struct Toolbox {
+    pub lol_api_fetcher: lol::api_fetcher::BoundedHttpFetcher,
+    pub storage: Box<dyn BotStorage>,
+    pub discord_client: common::discord_rest::DiscordRestClient
+}
+
+/**
+ * The input the EventBridge schedule sends us, saying which job to run
+ */
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ScheduledEvent {
+    pub job: String
+}
@@ -0,0 +1,42 @@
+use std::collections::HashSet;
+use common::date_range::DateRange;
+use common::player_stats::PlayedGame;
+use crate::models;
+
+/**
+ * Brings the player's stored games up to date for the given range and returns them. Only the games that aren't
+ * stored yet are fetched, and stored games that ended before the range are pruned so the partition stays small.
+ */
+pub async fn sync_played_games(toolbox: &models::Toolbox, api_key: &str, puuid: &str, range: &DateRange) -> Result<Vec<PlayedGame>, String> {
+    let storage = toolbox.storage.as_ref();
+
+    let game_ids = lol::get_game_ids(&toolbox.lol_api_fetcher, api_key, "europe", puuid, range, &lol::models::GameIdsFilter::default()).await
+        .map_err(|err| format!("{:?}", err))?;
+    let stored_games = common::player_stats::get_played_games(storage, puuid).await
+        .map_err(|err| format!("{:?}", err))?;
+
+    let stored_ids: HashSet<&str> = stored_games.iter().map(|game| game.match_id.as_str()).collect();
+    let new_ids: Vec<String> = game_ids.into_iter().filter(|game_id| !stored_ids.contains(game_id.as_str())).collect();
+
+    let new_games = lol::fetch_game_summaries(&toolbox.lol_api_fetcher, api_key, "europe", puuid, new_ids).await
+        .map_err(|err| format!("{:?}", err))?;
+
+    let mut games: Vec<PlayedGame> = Vec::new();
+    for summary in new_games.iter() {
+        let game = PlayedGame::from_summary(summary);
+        common::player_stats::store_played_game(storage, puuid, &game).await
+            .map_err(|err| format!("{:?}", err))?;
+        games.push(game);
+    }
+
+    let range_start_millis = range.start * 1000;
+    for game in stored_games.into_iter() {
+        if game.game_end_timestamp >= range_start_millis {
+            games.push(game);
+        } else if let Err(err) = common::player_stats::delete_played_game(storage, puuid, &game.match_id).await {
+            println!("Could not prune game {} for {}: {:?}", game.match_id, puuid, err);
+        }
+    }
+
+    return Ok(games);
+}
@@ -0,0 +1,90 @@
+use std::env;
+use common::discord_rest::MessagePayload;
+use common::playtime_budgets::PlaytimeBudget;
+use crate::models;
+
+/**
+ * Warns everyone who has gone over their playtime budget this period, once per period. A player's games are only
+ * counted again once they've played a new one.
+ */
+pub async fn check_playtime_budgets(toolbox: &models::Toolbox) {
+    let api_key = match env::var("LOL_API_KEY") {
+        Ok(key) => key,
+        Err(_) => {
+            println!("Missing LOL API key");
+            return;
+        }
+    };
+
+    let budgets = match common::playtime_budgets::get_budgets(toolbox.storage.as_ref()).await {
+        Ok(budgets) => budgets,
+        Err(err) => {
+            println!("Could not get playtime budgets: {:?}", err);
+            return;
+        }
+    };
+
+    for budget in budgets.into_iter() {
+        if let Err(err) = check_playtime_budget(toolbox, &api_key, budget).await {
+            println!("Could not check playtime budget: {}", err);
+        }
+    }
+}
+
+async fn check_playtime_budget(toolbox: &models::Toolbox, api_key: &str, budget: PlaytimeBudget) -> Result<(), String> {
+    let now = common::date_range::now_seconds().map_err(|err| format!("{}", err))?;
+    let period_start = budget.period.start(now);
+
+    if budget.warned_period_start == Some(period_start) {
+        return Ok(());
+    }
+
+    let game_ids = lol::get_game_ids(&toolbox.lol_api_fetcher, api_key, "europe", &budget.puuid, &budget.period.range(now), &lol::models::GameIdsFilter::default()).await
+        .map_err(|err| format!("{:?}", err))?;
+
+    // Game IDs are newest first, so if the first hasn't changed there's nothing new to count
+    let latest_match_id = match game_ids.first() {
+        None => return Ok(()),
+        Some(match_id) if budget.last_checked_match_id.as_ref() == Some(match_id) => return Ok(()),
+        Some(match_id) => match_id.to_string()
+    };
+
+    let games = lol::fetch_game_summaries(&toolbox.lol_api_fetcher, api_key, "europe", &budget.puuid, game_ids).await
+        .map_err(|err| format!("{:?}", err))?;
+    let played_millis: u64 = games.iter().map(|game| game.game_duration_millis).sum();
+
+    let mut warned_period_start = None;
+
+    if played_millis > budget.limit_millis {
+        let message = format!(
+            "{} has played for {} {}, over their {} budget of {}. Time to go and touch some grass.",
+            common::linked_accounts::format_member(&budget.discord_user_id, &budget.player_name),
+            common::date_range::format_duration(played_millis),
+            budget.period.description(),
+            budget.period.name(),
+            common::date_range::format_duration(budget.limit_millis)
+        );
+
+        let channel_id = match &budget.channel_id {
+            Some(channel_id) => channel_id.to_string(),
+            None => toolbox.discord_client.create_dm_channel(&budget.discord_user_id).await.map_err(|err| format!("{}", err))?.id
+        };
+
+        let post_result = toolbox.discord_client.create_channel_message(&channel_id, &MessagePayload::from_content(&message)).await;
+        println!("Warned {} about their playtime budget: {:?}", budget.player_name, post_result);
+
+        // Nothing is recorded if the warning didn't go out, so the next check counts the same games and tries again
+        post_result.map_err(|err| format!("{}", err))?;
+        warned_period_start = Some(period_start);
+    }
+
+    // Only what this check found is recorded, so a budget set again or cleared while we were checking stays as it is
+    let recorded = common::playtime_budgets::record_budget_check(toolbox.storage.as_ref(), &budget, &latest_match_id, warned_period_start).await
+        .map_err(|err| format!("{:?}", err))?;
+
+    if !recorded {
+        println!("{}'s playtime budget changed while it was being checked", budget.player_name);
+    }
+
+    return Ok(());
+}
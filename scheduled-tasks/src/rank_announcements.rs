@@ -0,0 +1,213 @@
+use common::discord_rest::MessagePayload;
+use common::guild_config::{GuildConfig, RankAnnouncementThreshold};
+use common::linked_accounts::LinkedAccount;
+use lol::models::LeagueEntry;
+use crate::models;
+
+const ANNOUNCED_QUEUE_TYPES: [&str; 2] = ["RANKED_SOLO_5x5", "RANKED_FLEX_SR"];
+
+/**
+ * Announces the rank changes of linked members in every server that turned announcements on with /announcements.
+ * Ranks are compared against what was last announced in the server, rather than the previous snapshot, so that
+ * changes too small to announce add up until they are worth announcing.
+ */
+pub async fn announce_rank_changes(toolbox: &models::Toolbox) {
+    let configs = match common::guild_config::get_guild_configs(toolbox.storage.as_ref()).await {
+        Ok(configs) => configs,
+        Err(err) => {
+            println!("Could not get guild configs: {:?}", err);
+            return;
+        }
+    };
+
+    for config in configs.iter() {
+        let channel_id = match &config.rank_announcement_channel_id {
+            None => continue,
+            Some(channel_id) => channel_id
+        };
+
+        let accounts = match common::linked_accounts::get_linked_accounts(toolbox.storage.as_ref(), &config.guild_id).await {
+            Ok(accounts) => accounts,
+            Err(err) => {
+                println!("Could not get linked accounts for guild {}: {:?}", config.guild_id, err);
+                continue;
+            }
+        };
+
+        for account in accounts.iter() {
+            for queue_type in ANNOUNCED_QUEUE_TYPES.iter() {
+                if let Err(err) = announce_rank_change(toolbox, config, channel_id, account, queue_type).await {
+                    println!("Could not announce {} rank change for {}: {}", queue_type, account.player_name, err);
+                }
+            }
+        }
+    }
+}
+
+async fn announce_rank_change(
+    toolbox: &models::Toolbox,
+    config: &GuildConfig,
+    channel_id: &str,
+    account: &LinkedAccount,
+    queue_type: &str) -> Result<(), String> {
+
+    let storage = toolbox.storage.as_ref();
+    let latest_snapshot = common::rank_history::get_latest_rank_snapshot(storage, &account.puuid, queue_type).await
+        .map_err(|err| format!("{:?}", err))?;
+
+    let current = match &latest_snapshot {
+        None => return Ok(()),
+        Some(snapshot) => &snapshot.entry
+    };
+
+    let announced = common::rank_announcements::get_announced_rank(storage, &config.guild_id, &account.puuid, queue_type).await
+        .map_err(|err| format!("{:?}", err))?;
+
+    let previous = match announced {
+        // The first rank we see is where we count changes from
+        None => return common::rank_announcements::store_announced_rank(storage, &config.guild_id, &account.puuid, current).await
+            .map_err(|err| format!("{:?}", err)),
+        Some(announced) => announced.entry
+    };
+
+    match create_rank_change_string(&previous, current, config) {
+        Some(change) => {
+            let message = format!(
+                "{} {}: {} - now {} {} LP",
+                common::linked_accounts::format_member(&account.discord_user_id, &account.player_name),
+                lol::rank::queue_display_name(queue_type),
+                change,
+                lol::rank::format_rank(current),
+                current.leaguePoints
+            );
+
+            let post_result = toolbox.discord_client.create_channel_message(channel_id, &MessagePayload::from_content(&message)).await;
+            println!("Announced rank change for {} in guild {}: {:?}", account.player_name, config.guild_id, post_result);
+        },
+        // Starting a promotion series isn't announced, but we need to remember it to tell if it was lost
+        None if previous.miniSeries.is_none() && current.miniSeries.is_some() => (),
+        None => return Ok(())
+    }
+
+    return common::rank_announcements::store_announced_rank(storage, &config.guild_id, &account.puuid, current).await
+        .map_err(|err| format!("{:?}", err));
+}
+
+/**
+ * Describes the change from the last announced rank if it passes the server's threshold, e.g.
+ * '+62 LP, promoted from Gold III' or '-18 LP, lost their promotion series'
+ */
+fn create_rank_change_string(previous: &LeagueEntry, current: &LeagueEntry, config: &GuildConfig) -> Option<String> {
+    let rank_change = lol::rank::format_rank_change(previous, current);
+    let league_points_change = (lol::rank::absolute_league_points(current) - lol::rank::absolute_league_points(previous)).unsigned_abs();
+
+    let tier_changed = previous.tier != current.tier;
+    let division_changed = lol::rank::division_ordinal(previous) != lol::rank::division_ordinal(current);
+    let series_lost = !division_changed && previous.miniSeries.is_some() && current.miniSeries.is_none();
+
+    let passes_threshold = match config.rank_announcement_threshold {
+        RankAnnouncementThreshold::Tier => tier_changed,
+        RankAnnouncementThreshold::Division => division_changed || series_lost
+    };
+
+    let passes_league_points = config.rank_announcement_min_league_points
+        .map(|min_league_points| league_points_change >= min_league_points)
+        .unwrap_or(false);
+
+    if series_lost && passes_threshold {
+        return Some(format!("{}, lost their promotion series", rank_change));
+    }
+
+    if passes_threshold || passes_league_points {
+        return Some(rank_change);
+    }
+
+    return None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(tier: &str, rank: &str, league_points: u64, in_series: bool) -> LeagueEntry {
+        let mini_series = if in_series {
+            serde_json::json!({"wins": 1, "losses": 0, "target": 2, "progress": "WNN"})
+        } else {
+            serde_json::Value::Null
+        };
+
+        return serde_json::from_value(serde_json::json!({
+            "tier": tier,
+            "rank": rank,
+            "leaguePoints": league_points,
+            "queueType": "RANKED_SOLO_5x5",
+            "miniSeries": mini_series
+        })).unwrap();
+    }
+
+    fn config(threshold: RankAnnouncementThreshold, min_league_points: Option<u64>) -> GuildConfig {
+        return GuildConfig {
+            rank_announcement_threshold: threshold,
+            rank_announcement_min_league_points: min_league_points,
+            ..GuildConfig::default()
+        };
+    }
+
+    #[test]
+    fn announces_divisions_only_at_the_division_threshold() {
+        let previous = entry("GOLD", "III", 50, false);
+        let current = entry("GOLD", "II", 10, false);
+
+        assert_eq!(create_rank_change_string(&previous, &current, &config(RankAnnouncementThreshold::Tier, None)), None);
+        assert_eq!(
+            create_rank_change_string(&previous, &current, &config(RankAnnouncementThreshold::Division, None)),
+            Some("+60 LP, promoted from Gold III".to_string())
+        );
+    }
+
+    #[test]
+    fn announces_tiers_at_both_thresholds() {
+        let previous = entry("PLATINUM", "IV", 10, false);
+        let current = entry("GOLD", "I", 80, false);
+
+        for threshold in [RankAnnouncementThreshold::Tier, RankAnnouncementThreshold::Division] {
+            assert_eq!(
+                create_rank_change_string(&previous, &current, &config(threshold, None)),
+                Some("-30 LP, demoted from Platinum IV".to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn announces_a_lost_series_at_the_division_threshold() {
+        let previous = entry("GOLD", "I", 100, true);
+        let current = entry("GOLD", "I", 80, false);
+
+        assert_eq!(
+            create_rank_change_string(&previous, &current, &config(RankAnnouncementThreshold::Division, None)),
+            Some("-20 LP, lost their promotion series".to_string())
+        );
+        assert_eq!(create_rank_change_string(&previous, &current, &config(RankAnnouncementThreshold::Tier, None)), None);
+    }
+
+    #[test]
+    fn does_not_announce_starting_a_series() {
+        let previous = entry("GOLD", "I", 85, false);
+        let current = entry("GOLD", "I", 100, true);
+
+        assert_eq!(create_rank_change_string(&previous, &current, &config(RankAnnouncementThreshold::Division, None)), None);
+    }
+
+    #[test]
+    fn announces_league_points_changes_of_at_least_the_minimum() {
+        let previous = entry("GOLD", "II", 20, false);
+        let current = entry("GOLD", "II", 70, false);
+
+        assert_eq!(
+            create_rank_change_string(&previous, &current, &config(RankAnnouncementThreshold::Tier, Some(50))),
+            Some("+50 LP".to_string())
+        );
+        assert_eq!(create_rank_change_string(&previous, &current, &config(RankAnnouncementThreshold::Tier, Some(51))), None);
+        assert_eq!(create_rank_change_string(&current, &previous, &config(RankAnnouncementThreshold::Tier, Some(50))), Some("-50 LP".to_string()));
+    }
+}
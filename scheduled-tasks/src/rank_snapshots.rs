@@ -0,0 +1,38 @@
+use std::env;
+use crate::models;
+
+/**
+ * Snapshots the rank of every player we've looked up the rank of before, so that we can report how their
+ * LP has changed even if nobody asks about them for a while
+ */
+pub async fn take_rank_snapshots(toolbox: &models::Toolbox) {
+    let api_key = match env::var("LOL_API_KEY") {
+        Ok(key) => key,
+        Err(_) => {
+            println!("Missing LOL API key");
+            return;
+        }
+    };
+
+    let players = match common::rank_history::get_tracked_players(toolbox.storage.as_ref()).await {
+        Ok(players) => players,
+        Err(err) => {
+            println!("Could not get tracked players: {:?}", err);
+            return;
+        }
+    };
+
+    println!("Taking rank snapshots for {} players", players.len());
+
+    for player in players.iter() {
+        let rankings = lol::get_league_rankings(&toolbox.lol_api_fetcher, "euw1", player.summoner_id.to_string(), &api_key).await;
+
+        match rankings {
+            Err(err) => println!("Could not get rankings for {}: {:?}", player.player_name, err),
+            Ok(entries) => {
+                let store_result = common::rank_history::store_rank_snapshots(toolbox.storage.as_ref(), &player.puuid, &entries).await;
+                println!("Stored rank snapshot for {}: {:?}", player.player_name, store_result);
+            }
+        }
+    }
+}
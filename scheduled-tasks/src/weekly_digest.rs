@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+use std::env;
+use tokio::time::{timeout_at, Instant};
+use common::discord_rest::MessagePayload;
+use common::guild_config::GuildConfig;
+use common::linked_accounts::{format_member, LinkedAccount};
+use common::player_stats::{PlayedGame, WeeklyStats};
+use crate::models;
+use crate::played_games;
+
+/**
+ * Weekly stats older than this are left out of the digest rather than reported as this week's. The leaderboard
+ * refresh normally keeps them a few hours old at most.
+ */
+const STATS_MAX_AGE_SECONDS: u64 = 86400;
+
+/**
+ * What a linked member of a server did over the last week
+ */
+struct MemberWeek {
+    account: LinkedAccount,
+    games: Vec<PlayedGame>,
+
+    /**
+     * The stats the leaderboard refresh stored, if they are recent enough
+     */
+    weekly_stats: Option<WeeklyStats>,
+
+    /**
+     * How many LP they gained (or lost) in solo queue, counting divisions and tiers
+     */
+    league_points_change: Option<i64>
+}
+
+/**
+ * Posts a summary of the last week to every server that opted in with /digest. Hours come from the stats the
+ * leaderboard refresh stores, and games from the stored per-match results, so only games since the last refresh
+ * are fetched.
+ */
+pub async fn post_weekly_digests(toolbox: &models::Toolbox, deadline: Instant) {
+    let api_key = match env::var("LOL_API_KEY") {
+        Ok(key) => key,
+        Err(_) => {
+            println!("Missing LOL API key");
+            return;
+        }
+    };
+
+    let now = match common::date_range::now_seconds() {
+        Ok(now) => now,
+        Err(err) => {
+            println!("Could not get the time: {:?}", err);
+            return;
+        }
+    };
+
+    let configs = match common::guild_config::get_guild_configs(toolbox.storage.as_ref()).await {
+        Ok(configs) => configs,
+        Err(err) => {
+            println!("Could not get guild configs: {:?}", err);
+            return;
+        }
+    };
+
+    // Members of several servers only have their games synced once
+    let mut games_by_puuid: HashMap<String, Vec<PlayedGame>> = HashMap::new();
+
+    for config in configs.iter() {
+        let channel_id = match &config.digest_channel_id {
+            None => continue,
+            Some(channel_id) => channel_id
+        };
+
+        let message = match create_digest_message(toolbox, &api_key, config, now, deadline, &mut games_by_puuid).await {
+            Ok(message) => message,
+            Err(err) => {
+                println!("Could not create digest for guild {}: {}", config.guild_id, err);
+                continue;
+            }
+        };
+
+        let post_result = toolbox.discord_client.create_channel_message(channel_id, &MessagePayload::from_content(&message)).await;
+        println!("Posted digest for guild {}: {:?}", config.guild_id, post_result);
+    }
+}
+
+async fn create_digest_message(
+    toolbox: &models::Toolbox,
+    api_key: &str,
+    config: &GuildConfig,
+    now: u64,
+    deadline: Instant,
+    games_by_puuid: &mut HashMap<String, Vec<PlayedGame>>) -> Result<String, String> {
+
+    let week = common::date_range::last_days(7, now);
+    let accounts = common::linked_accounts::get_linked_accounts(toolbox.storage.as_ref(), &config.guild_id).await
+        .map_err(|err| format!("{:?}", err))?;
+
+    let mut members: Vec<MemberWeek> = Vec::new();
+
+    for account in accounts.into_iter() {
+        if !games_by_puuid.contains_key(&account.puuid) {
+            match get_games(toolbox, api_key, &account.puuid, &week, deadline).await {
+                Ok(games) => { games_by_puuid.insert(account.puuid.to_string(), games); },
+                Err(err) => {
+                    println!("Could not get games for {}: {}", account.player_name, err);
+                    continue;
+                }
+            }
+        }
+
+        let weekly_stats = common::player_stats::get_weekly_stats(toolbox.storage.as_ref(), &account.puuid).await
+            .unwrap_or(None)
+            .filter(|stats| stats.updated_at + STATS_MAX_AGE_SECONDS > now);
+
+        let snapshots = common::rank_history::get_rank_snapshots(toolbox.storage.as_ref(), &account.puuid, "RANKED_SOLO_5x5").await
+            .unwrap_or(Vec::new());
+        let league_points_change = match (common::rank_history::find_baseline_snapshot(&snapshots, week.start), snapshots.last()) {
+            (Some(baseline), Some(latest)) => Some(lol::rank::absolute_league_points(&latest.entry) - lol::rank::absolute_league_points(&baseline.entry)),
+            _ => None
+        };
+
+        members.push(MemberWeek {
+            games: games_by_puuid.get(&account.puuid).cloned().unwrap_or(Vec::new()),
+            account: account,
+            weekly_stats: weekly_stats,
+            league_points_change: league_points_change
+        });
+    }
+
+    let mut message = format!("**Weekly digest** for the 7 days up to {}\n", common::date_range::format_timestamp(now));
+
+    if members.iter().all(|member| member.games.is_empty() && member.weekly_stats.as_ref().map(|stats| stats.games).unwrap_or(0) == 0) {
+        message.push_str("Nobody played any games this week.\n");
+        return Ok(message);
+    }
+
+    message.push_str(&create_most_hours_string(&members));
+    message.push_str(&create_biggest_climber_string(&members));
+    message.push_str(&create_worst_kda_string(&members));
+    message.push_str(&create_most_played_champion_string(&members));
+
+    return Ok(message);
+}
+
+/**
+ * Syncs the member's stored games, falling back to what is already stored once we're out of time
+ */
+async fn get_games(toolbox: &models::Toolbox, api_key: &str, puuid: &str, range: &common::date_range::DateRange, deadline: Instant) -> Result<Vec<PlayedGame>, String> {
+    if let Ok(synced) = timeout_at(deadline, played_games::sync_played_games(toolbox, api_key, puuid, range)).await {
+        return synced;
+    }
+
+    println!("Ran out of time syncing games for {}, using the stored ones", puuid);
+    let stored_games = common::player_stats::get_played_games(toolbox.storage.as_ref(), puuid).await
+        .map_err(|err| format!("{:?}", err))?;
+
+    return Ok(stored_games.into_iter().filter(|game| game.game_end_timestamp >= range.start * 1000).collect());
+}
+
+fn create_most_hours_string(members: &Vec<MemberWeek>) -> String {
+    let most_hours = members.iter()
+        .filter_map(|member| member.weekly_stats.as_ref().map(|stats| (member, stats)))
+        .max_by_key(|(_, stats)| stats.played_for_millis);
+
+    return match most_hours {
+        Some((member, stats)) if stats.played_for_millis > 0 => format!(
+            "Most hours played: {} - {:.1} hours over {} games\n",
+            format_member(&member.account.discord_user_id, &member.account.player_name),
+            stats.played_for_millis as f64 / 3600000.0,
+            stats.games
+        ),
+        _ => "".to_string()
+    };
+}
+
+fn create_biggest_climber_string(members: &Vec<MemberWeek>) -> String {
+    let climber = members.iter()
+        .filter_map(|member| member.league_points_change.map(|change| (member, change)))
+        .max_by_key(|(_, change)| *change);
+
+    return match climber {
+        Some((member, change)) if change > 0 => format!("Biggest LP climber: {} - +{} LP in solo queue\n", format_member(&member.account.discord_user_id, &member.account.player_name), change),
+        _ => "Nobody climbed in solo queue this week\n".to_string()
+    };
+}
+
+fn create_worst_kda_string(members: &Vec<MemberWeek>) -> String {
+    let worst = members.iter()
+        .flat_map(|member| member.games.iter().map(move |game| (member, game)))
+        .min_by(|(_, a), (_, b)| lol::stats::kda(a.kills, a.deaths, a.assists).partial_cmp(&lol::stats::kda(b.kills, b.deaths, b.assists)).unwrap_or(std::cmp::Ordering::Equal));
+
+    return match worst {
+        None => "".to_string(),
+        Some((member, game)) => format!(
+            "Worst KDA game: {} - {}/{}/{} on {} ({:.2} KDA)\n",
+            format_member(&member.account.discord_user_id, &member.account.player_name),
+            game.kills,
+            game.deaths,
+            game.assists,
+            game.champion_name,
+            lol::stats::kda(game.kills, game.deaths, game.assists)
+        )
+    };
+}
+
+/**
+ * The champion played in the most games by the server's members, e.g. 'Ahri (14 games by 3 members)'
+ */
+fn create_most_played_champion_string(members: &Vec<MemberWeek>) -> String {
+    let mut champions: HashMap<String, (u64, u64)> = HashMap::new();
+
+    for member in members.iter() {
+        let mut member_champions: HashMap<String, u64> = HashMap::new();
+        for game in member.games.iter() {
+            *member_champions.entry(game.champion_name.to_string()).or_insert(0) += 1;
+        }
+
+        for (champion, games) in member_champions.into_iter() {
+            let (total_games, players) = champions.entry(champion).or_insert((0, 0));
+            *total_games += games;
+            *players += 1;
+        }
+    }
+
+    let most_played = champions.into_iter().max_by(|(a_name, (a_games, _)), (b_name, (b_games, _))| a_games.cmp(b_games).then(b_name.cmp(a_name)));
+
+    return match most_played {
+        None => "".to_string(),
+        Some((champion, (games, players))) => format!(
+            "Most played champion: {} ({} {} by {} {})\n",
+            champion,
+            games,
+            if games == 1 {"game"} else {"games"},
+            players,
+            if players == 1 {"member"} else {"members"}
+        )
+    };
+}
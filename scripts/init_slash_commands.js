@@ -1,15 +1,88 @@
 const {REST} = require('@discordjs/rest');
 const {Routes} = require('discord-api-types/v9');
 
-const generateDaysChoices = (maxNumberOfDays) => {
-  const days = [...Array(maxNumberOfDays).keys()];
+const rangeChoices = [
+  {"name": "This week", "value": "this_week"},
+  {"name": "This month", "value": "this_month"},
+  {"name": "Last month", "value": "last_month"},
+  {"name": "This season", "value": "this_season"},
+  {"name": "Last season", "value": "last_season"}
+];
+
+const dateRangeOptions = [{
+    "name": "days",
+    "description": "Over the last how many days (7 if no other dates are given).",
+    "required": false,
+    "type": 4,
+    "min_value": 1,
+    "max_value": 365
+  },{
+    "name": "range",
+    "description": "Over a named period instead of a number of days.",
+    "required": false,
+    "type": 3,
+    "choices": rangeChoices
+  },{
+    "name": "since",
+    "description": "From this date, e.g. 2024-01-31.",
+    "required": false,
+    "type": 3
+  },{
+    "name": "until",
+    "description": "Up to and including this date, e.g. 2024-02-29.",
+    "required": false,
+    "type": 3
+  }];
+
+const queueChoices = [
+  {"name": "Ranked Solo/Duo", "value": 420},
+  {"name": "Ranked Flex", "value": 440},
+  {"name": "Normal Draft", "value": 400},
+  {"name": "ARAM", "value": 450},
+  {"name": "Arena", "value": 1700}
+];
+
+const rankedQueueChoices = queueChoices.filter(choice => choice.value === 420 || choice.value === 440);
 
-  return days.map(day => (
-    {
-      "name": '' + (day + 1),
-      "value": day + 1
-    }));
-}
+const roleChoices = [
+  {"name": "Top", "value": "TOP"},
+  {"name": "Jungle", "value": "JUNGLE"},
+  {"name": "Mid", "value": "MIDDLE"},
+  {"name": "Bot", "value": "BOTTOM"},
+  {"name": "Support", "value": "UTILITY"}
+];
+
+const filterOptions = (queues) => [{
+    "name": "queue",
+    "description": "Only count games in this queue.",
+    "required": false,
+    "type": 4,
+    "choices": queues
+  },{
+    "name": "champion",
+    "description": "Only count games on this champion.",
+    "required": false,
+    "type": 3,
+    "autocomplete": true
+  },{
+    "name": "role",
+    "description": "Only count games played in this role.",
+    "required": false,
+    "type": 3,
+    "choices": roleChoices
+  }];
+
+const detailOption = {
+  "name": "detail",
+  "description": "How much to show about each game.",
+  "required": false,
+  "type": 3,
+  "choices": [
+    {"name": "Basic (K/D/A)", "value": "basic"},
+    {"name": "Stats (CS/min, kill participation, damage share)", "value": "stats"},
+    {"name": "Full (also gold share, vision and multikills)", "value": "full"}
+  ]
+};
 
 const commands = [
   {
@@ -23,12 +96,14 @@ const commands = [
         "type": 3,
         "autocomplete": true
     },{
-        "name": "days",
-        "description": "Over the last how many days.",
-        "required": true,
-        "type": 4,
-        "choices": generateDaysChoices(7)
-    }]
+        "name": "private",
+        "description": "Only show the summary to you.",
+        "required": false,
+        "type": 5
+    },
+    detailOption,
+    ...dateRangeOptions,
+    ...filterOptions(queueChoices)]
   },
   {
     name: 'ranked',
@@ -41,13 +116,268 @@ const commands = [
         "type": 3,
         "autocomplete": true
     },{
-      "name": "days",
-      "description": "Over the last how many days.",
+      "name": "private",
+      "description": "Only show the summary to you.",
+      "required": false,
+      "type": 5
+    },
+    detailOption,
+    ...dateRangeOptions,
+    ...filterOptions(rankedQueueChoices)]
+  },
+  {
+    name: 'rank',
+    description: `Show a player's rank in every ranked queue.`,
+    type: 1,
+    options: [{
+      "name": "user",
+      "description": "The league of legends username for the user.",
+      "required": true,
+      "type": 3,
+      "autocomplete": true
+    },{
+      "name": "private",
+      "description": "Only show the ranks to you.",
+      "required": false,
+      "type": 5
+    }]
+  },
+  {
+    name: 'live',
+    description: `Show the game a player is currently in.`,
+    type: 1,
+    options: [{
+      "name": "user",
+      "description": "The league of legends username for the user.",
+      "required": true,
+      "type": 3,
+      "autocomplete": true
+    },{
+      "name": "private",
+      "description": "Only show the game to you.",
+      "required": false,
+      "type": 5
+    }]
+  },
+  {
+    name: 'mastery',
+    description: `Show a player's champion mastery.`,
+    type: 1,
+    options: [{
+      "name": "user",
+      "description": "The league of legends username for the user.",
+      "required": true,
+      "type": 3,
+      "autocomplete": true
+    },{
+      "name": "champion",
+      "description": "Only show mastery of this champion.",
+      "required": false,
+      "type": 3,
+      "autocomplete": true
+    },{
+      "name": "count",
+      "description": "How many of the player's top champions to show (default 10).",
+      "required": false,
+      "type": 4,
+      "min_value": 1,
+      "max_value": 25
+    },{
+      "name": "private",
+      "description": "Only show the mastery to you.",
+      "required": false,
+      "type": 5
+    }]
+  },
+  {
+    name: 'compare',
+    description: `Compare how two players have been doing.`,
+    type: 1,
+    options: [{
+      "name": "user",
+      "description": "The league of legends username for the first player.",
+      "required": true,
+      "type": 3,
+      "autocomplete": true
+    },{
+      "name": "other_user",
+      "description": "The league of legends username for the second player.",
+      "required": true,
+      "type": 3,
+      "autocomplete": true
+    },{
+      "name": "private",
+      "description": "Only show the comparison to you.",
+      "required": false,
+      "type": 5
+    },
+    ...dateRangeOptions]
+  },
+  {
+    name: 'link',
+    description: `Link your League account so the bot knows who you are in this server.`,
+    type: 1,
+    options: [{
+      "name": "user",
+      "description": "Your league of legends username.",
+      "required": true,
+      "type": 3,
+      "autocomplete": true
+    }]
+  },
+  {
+    name: 'leaderboard',
+    description: `Rank this server's linked members.`,
+    type: 1,
+    options: [{
+      "name": "sort",
+      "description": "What to rank members by (default solo queue rank).",
+      "required": false,
+      "type": 3,
+      "choices": [
+        {"name": "Solo queue rank", "value": "rank"},
+        {"name": "Games played this week", "value": "games"},
+        {"name": "Winrate this week", "value": "winrate"},
+        {"name": "Hours played this week", "value": "hours"}
+      ]
+    }]
+  },
+  {
+    name: 'digest',
+    description: `Post a weekly summary of this server's games to a channel.`,
+    type: 1,
+    // Only members who can manage the server can use it
+    default_member_permissions: "32",
+    dm_permission: false,
+    options: [{
+      "name": "channel",
+      "description": "The channel to post the digest in (default this channel).",
+      "required": false,
+      "type": 7,
+      "channel_types": [0]
+    },{
+      "name": "enabled",
+      "description": "Set to false to stop posting the digest.",
+      "required": false,
+      "type": 5
+    }]
+  },
+  {
+    name: 'announcements',
+    description: `Announce linked members' rank changes in a channel.`,
+    type: 1,
+    // Only members who can manage the server can use it
+    default_member_permissions: "32",
+    dm_permission: false,
+    options: [{
+      "name": "channel",
+      "description": "The channel to announce rank changes in (default this channel).",
+      "required": false,
+      "type": 7,
+      "channel_types": [0]
+    },{
+      "name": "threshold",
+      "description": "Which rank changes to announce (default division).",
+      "required": false,
+      "type": 3,
+      "choices": [
+        {"name": "Tier changes", "value": "tier"},
+        {"name": "Division changes and promotion series", "value": "division"}
+      ]
+    },{
+      "name": "min_lp",
+      "description": "Also announce gaining or losing this much LP (0 to turn off).",
+      "required": false,
+      "type": 4,
+      "min_value": 0,
+      "max_value": 1000
+    },{
+      "name": "enabled",
+      "description": "Set to false to stop announcing rank changes.",
+      "required": false,
+      "type": 5
+    }]
+  },
+  {
+    name: 'track',
+    description: `Post in a channel whenever a player finishes a game.`,
+    type: 1,
+    // Only members who can manage the server can use it, since it posts in (or stops posting in) any channel
+    default_member_permissions: "32",
+    dm_permission: false,
+    options: [{
+      "name": "user",
+      "description": "The league of legends username for the user.",
       "required": true,
+      "type": 3,
+      "autocomplete": true
+    },{
+      "name": "channel",
+      "description": "The channel to post the player's games in (default this channel).",
+      "required": false,
+      "type": 7,
+      "channel_types": [0]
+    },{
+      "name": "stop",
+      "description": "Stop posting the player's games in the channel.",
+      "required": false,
+      "type": 5
+    },{
+      "name": "private",
+      "description": "Only show the response to you.",
+      "required": false,
+      "type": 5
+    }]
+  },
+  {
+    name: 'budget',
+    description: `Set, view or clear how long you want to play for each day or week.`,
+    type: 1,
+    dm_permission: false,
+    options: [{
+      "name": "action",
+      "description": "What to do with your budget (default view).",
+      "required": false,
+      "type": 3,
+      "choices": [
+        {"name": "Set", "value": "set"},
+        {"name": "View", "value": "view"},
+        {"name": "Clear", "value": "clear"}
+      ]
+    },{
+      "name": "hours",
+      "description": "How many hours you want to play for.",
+      "required": false,
       "type": 4,
-      "choices": generateDaysChoices(7)
+      "min_value": 1,
+      "max_value": 168
+    },{
+      "name": "period",
+      "description": "Whether the budget is per day or per week (default daily).",
+      "required": false,
+      "type": 3,
+      "choices": [
+        {"name": "Daily", "value": "daily"},
+        {"name": "Weekly", "value": "weekly"}
+      ]
+    },{
+      "name": "user",
+      "description": "The league of legends username to budget (default your linked account).",
+      "required": false,
+      "type": 3,
+      "autocomplete": true
+    },{
+      "name": "dm",
+      "description": "Warn you by DM instead of in this channel.",
+      "required": false,
+      "type": 5
     }]
   },
+  {
+    name: 'unlink',
+    description: `Unlink your League account in this server.`,
+    type: 1
+  },
 ];
 
 const rest = new REST({version: '9'}).setToken(process.env.DISCORD_TOKEN);